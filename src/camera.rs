@@ -0,0 +1,151 @@
+use crate::{VIEW_HEIGHT, VIEW_WIDTH};
+
+// The most window pixels a single cell can be blown up to; zooming goes in
+// powers of two up to it
+const MAX_ZOOM: usize = 8;
+
+// The color of the part of the view past the edge of the grid
+const OUTSIDE_COLOR: [u8; 4] = [0x10, 0x10, 0x10, 0xFF];
+
+/// Which part of the grid is shown in the view below the toolbar, and how
+/// far it's zoomed in
+pub struct Camera {
+    // How many cells wide and tall the grid being shown is
+    grid: (usize, usize),
+    // The (possibly fractional) cell at the top-left corner of the view
+    origin: (f32, f32),
+    zoom: usize,
+    // Where the view was when the whole of it was last copied, if it still is
+    drawn: Option<((f32, f32), usize)>,
+}
+
+impl Camera {
+    pub fn new(grid: (usize, usize)) -> Self {
+        Self {
+            grid,
+            origin: (0.0, 0.0),
+            zoom: 1,
+            drawn: None,
+        }
+    }
+
+    /// Whether a cell is on the grid rather than past its edge
+    pub fn on_grid(&self, (x, y): (usize, usize)) -> bool {
+        x < self.grid.0 && y < self.grid.1
+    }
+
+    /// The cell shown at a point in the view. It can be past the edge of the
+    /// grid when the grid doesn't fill the view.
+    pub fn cell_at(&self, (x, y): (usize, usize)) -> (usize, usize) {
+        (
+            (self.origin.0 + x as f32 / self.zoom as f32) as usize,
+            (self.origin.1 + y as f32 / self.zoom as f32) as usize,
+        )
+    }
+
+    /// Moves the view by a distance measured in view pixels
+    pub fn pan(&mut self, (dx, dy): (f32, f32)) {
+        self.origin.0 += dx / self.zoom as f32;
+        self.origin.1 += dy / self.zoom as f32;
+        self.clamp();
+    }
+
+    /// Zooms in or out a step, keeping the cell under a point in the view
+    /// where it is
+    pub fn zoom_at(&mut self, (x, y): (usize, usize), zoom_in: bool) {
+        let zoom = if zoom_in {
+            (self.zoom * 2).min(MAX_ZOOM)
+        } else {
+            (self.zoom / 2).max(1)
+        };
+        let (x, y) = (x as f32, y as f32);
+        self.origin.0 += x / self.zoom as f32 - x / zoom as f32;
+        self.origin.1 += y / self.zoom as f32 - y / zoom as f32;
+        self.zoom = zoom;
+        self.clamp();
+    }
+
+    // Keeps the view from wandering off the grid
+    fn clamp(&mut self) {
+        let visible = (
+            VIEW_WIDTH as f32 / self.zoom as f32,
+            VIEW_HEIGHT as f32 / self.zoom as f32,
+        );
+        self.origin.0 = self
+            .origin
+            .0
+            .clamp(0.0, (self.grid.0 as f32 - visible.0).max(0.0));
+        self.origin.1 = self
+            .origin
+            .1
+            .clamp(0.0, (self.grid.1 as f32 - visible.1).max(0.0));
+    }
+
+    /// Copies the visible part of `canvas`, which holds the whole grid, into
+    /// the view's part of the window's frame
+    pub fn draw(&mut self, canvas: &[u8], frame: &mut [u8]) {
+        self.draw_area(
+            canvas,
+            frame,
+            (0, 0),
+            (VIEW_WIDTH as usize, VIEW_HEIGHT as usize),
+        );
+        self.drawn = Some((self.origin, self.zoom));
+    }
+
+    /// Copies just the cells from each region's top-left to its bottom-right
+    /// corner out of `canvas`, or the whole view if it has moved since it
+    /// was last drawn
+    pub fn draw_regions(
+        &mut self,
+        canvas: &[u8],
+        frame: &mut [u8],
+        regions: impl IntoIterator<Item = ((usize, usize), (usize, usize))>,
+    ) {
+        if self.drawn != Some((self.origin, self.zoom)) {
+            self.draw(canvas, frame);
+            return;
+        }
+        let zoom = self.zoom as f32;
+        // The view pixels showing cells `start..=end` along one axis, with a
+        // pixel to spare at each end so rounding never leaves a gap
+        let pixels = |start: usize, end: usize, origin: f32, limit: u32| {
+            let first = ((start as f32 - origin) * zoom - 1.0).max(0.0) as usize;
+            let last = ((end as f32 + 1.0 - origin) * zoom + 1.0).max(0.0) as usize;
+            first.min(limit as usize)..last.min(limit as usize)
+        };
+        for ((x0, y0), (x1, y1)) in regions {
+            let xs = pixels(x0.min(x1), x0.max(x1), self.origin.0, VIEW_WIDTH);
+            let ys = pixels(y0.min(y1), y0.max(y1), self.origin.1, VIEW_HEIGHT);
+            self.draw_area(canvas, frame, (xs.start, ys.start), (xs.end, ys.end));
+        }
+    }
+
+    /// Copies the view pixels from `(x0, y0)` up to but not including
+    /// `(x1, y1)` out of `canvas`, such as to clear away whatever was drawn
+    /// over them
+    pub fn draw_area(
+        &self,
+        canvas: &[u8],
+        frame: &mut [u8],
+        (x0, y0): (usize, usize),
+        (x1, y1): (usize, usize),
+    ) {
+        let (x1, y1) = (x1.min(VIEW_WIDTH as usize), y1.min(VIEW_HEIGHT as usize));
+        let (x0, y0) = (x0.min(x1), y0.min(y1));
+        for y in y0..y1 {
+            let row = y * VIEW_WIDTH as usize;
+            for (x, pixel) in
+                (x0..x1).zip(frame[(row + x0) * 4..(row + x1) * 4].chunks_exact_mut(4))
+            {
+                let (cell_x, cell_y) = self.cell_at((x, y));
+                if self.on_grid((cell_x, cell_y)) {
+                    let i = (cell_y * self.grid.0 + cell_x) * 4;
+                    pixel.copy_from_slice(&canvas[i..i + 4]);
+                } else {
+                    pixel.copy_from_slice(&OUTSIDE_COLOR);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{CHUNK_SIZE, CHUNK_SLEEP_TICKS};
+
+/// How long it has been since anything changed in each chunk of the grid.
+/// A chunk is awake while it or one of its neighbors has changed recently
+/// enough, and asleep once they've all settled.
+#[derive(Clone)]
+pub struct Chunks {
+    wide: usize,
+    high: usize,
+    quiet: Vec<u32>,
+    awake: Vec<bool>,
+    // The top left cell of each chunk that woke up at the last step
+    woken: Vec<(usize, usize)>,
+}
+
+impl Chunks {
+    pub fn new(width: usize, height: usize) -> Self {
+        let (wide, high) = (width.div_ceil(CHUNK_SIZE), height.div_ceil(CHUNK_SIZE));
+        Self {
+            wide,
+            high,
+            quiet: vec![0; wide * high],
+            awake: vec![true; wide * high],
+            woken: Vec::new(),
+        }
+    }
+
+    fn chunk(&self, (x, y): (usize, usize)) -> usize {
+        (y / CHUNK_SIZE) * self.wide + x / CHUNK_SIZE
+    }
+
+    /// Notes that something changed in a cell
+    pub fn touch(&mut self, pos: (usize, usize)) {
+        let chunk = self.chunk(pos);
+        self.quiet[chunk] = 0;
+    }
+
+    pub fn wake_all(&mut self) {
+        self.quiet.fill(0);
+    }
+
+    pub fn awake(&self, pos: (usize, usize)) -> bool {
+        self.awake[self.chunk(pos)]
+    }
+
+    /// The top left cell of each chunk that was asleep until the last step
+    pub fn woken(&self) -> &[(usize, usize)] {
+        &self.woken
+    }
+
+    /// Counts another tick, and works out which chunks are awake for it
+    pub fn step(&mut self) {
+        self.woken.clear();
+        for quiet in &mut self.quiet {
+            *quiet = quiet.saturating_add(1);
+        }
+        let (wide, high) = (self.wide, self.high);
+        for cy in 0..high {
+            for cx in 0..wide {
+                let mut awake = false;
+                for ny in cy.saturating_sub(1)..=(cy + 1).min(high - 1) {
+                    // Round the sides too, in case the world wraps
+                    for nx in [cx + wide - 1, cx, cx + 1] {
+                        let nx = nx % wide;
+                        awake |= self.quiet[ny * wide + nx] <= CHUNK_SLEEP_TICKS;
+                    }
+                }
+                if awake && !self.awake[cy * wide + cx] {
+                    self.woken.push((cx * CHUNK_SIZE, cy * CHUNK_SIZE));
+                }
+                self.awake[cy * wide + cx] = awake;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Kind, World};
+
+    #[test]
+    fn sleeping_chunks_wake_when_disturbed() {
+        let mut world = World::new();
+        world.sleeping = true;
+        // Keep the shelf up in the air
+        world.structural_integrity = false;
+        for x in 30..80 {
+            world.set_pixel((x, 100), Kind::Stone);
+        }
+        for y in 95..100 {
+            world.set_pixel((55, y), Kind::Sand);
+        }
+        // Long enough for the sand to settle and its chunks to fall asleep
+        for _ in 0..200 {
+            world.update();
+        }
+        let x = (30..80)
+            .find(|&x| world.particle((x, 99)).kind() == Kind::Sand)
+            .unwrap();
+        assert!(!world.chunks.awake((x, 99)));
+
+        // Knocking the floor out from under it should set it moving again
+        world.set_pixel((x, 100), Kind::Empty);
+        for _ in 0..100 {
+            world.update();
+        }
+        assert_ne!(world.particle((x, 99)).kind(), Kind::Sand);
+    }
+
+    #[test]
+    fn woken_chunks_move_on_the_tick_they_wake() {
+        // A chunk that sleeps for an odd number of ticks wakes on a tick of
+        // the other parity from the one its particles last had a turn on
+        for extra in 0..2 {
+            let mut world = World::with_seed(1);
+            world.sleeping = true;
+            world.structural_integrity = false;
+            for x in 90..110 {
+                world.set_pixel((x, 100), Kind::Stone);
+            }
+            world.set_pixel((100, 99), Kind::Sand);
+            while world.chunks.awake((100, 99)) {
+                world.update();
+            }
+            for _ in 0..extra {
+                world.update();
+            }
+
+            world.set_pixel((100, 100), Kind::Empty);
+            world.update();
+            assert_ne!(world.particle((100, 99)).kind(), Kind::Sand);
+        }
+    }
+}
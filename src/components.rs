@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use crate::Boundary;
+
+pub type ComponentId = u32;
+
+const NO_COMPONENT: ComponentId = 0;
+
+/// Incrementally tracks 4-connected groups of solid cells.
+///
+/// Insertions merge neighboring groups in place; removals relabel only the
+/// group the removed cell belonged to, so the cost of an edit is bounded by
+/// the size of the structure it touches rather than the size of the world.
+#[derive(Clone)]
+pub struct Components {
+    width: usize,
+    height: usize,
+    labels: Vec<ComponentId>,
+    // Cells are joined to their neighbors through this, so structures can
+    // reach round from one side of a wrapped world to the other
+    boundary: Boundary,
+    sizes: HashMap<ComponentId, usize>,
+    next_id: ComponentId,
+}
+
+impl Components {
+    pub fn new((width, height): (usize, usize), boundary: Boundary) -> Self {
+        Self {
+            width,
+            height,
+            labels: vec![NO_COMPONENT; width * height],
+            boundary,
+            sizes: HashMap::new(),
+            next_id: NO_COMPONENT + 1,
+        }
+    }
+
+    /// The boundary the labels were joined up through
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Forgets every group, as if every cell were empty
+    pub fn clear(&mut self) {
+        self.labels.fill(NO_COMPONENT);
+        self.sizes.clear();
+    }
+
+    fn index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.width + x
+    }
+
+    fn neighbors(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        let (boundary, size) = (self.boundary, (self.width, self.height));
+        [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .into_iter()
+            .filter_map(move |offset| boundary.offset(size, pos, offset))
+    }
+
+    fn fresh_id(&mut self) -> ComponentId {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(NO_COMPONENT + 1);
+        id
+    }
+
+    /// Flood fills the group containing `start` from label `from` to `to`,
+    /// returning the number of cells relabeled.
+    fn relabel(&mut self, start: (usize, usize), from: ComponentId, to: ComponentId) -> usize {
+        let mut count = 0;
+        let mut stack = vec![start];
+        while let Some(pos) = stack.pop() {
+            let i = self.index(pos);
+            if self.labels[i] != from {
+                continue;
+            }
+            self.labels[i] = to;
+            count += 1;
+            let neighbors = self.neighbors(pos);
+            stack.extend(neighbors.filter(|&n| self.labels[self.index(n)] == from));
+        }
+        count
+    }
+
+    pub fn component_at(&self, pos: (usize, usize)) -> Option<ComponentId> {
+        match self.labels[self.index(pos)] {
+            NO_COMPONENT => None,
+            id => Some(id),
+        }
+    }
+
+    pub fn component_size(&self, id: ComponentId) -> usize {
+        self.sizes.get(&id).copied().unwrap_or(0)
+    }
+
+    /// Every cell in the same group as `pos`, or nothing if `pos` isn't solid.
+    pub fn component_cells(&self, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let id = match self.component_at(pos) {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+
+        let mut visited = vec![false; self.labels.len()];
+        let mut cells = Vec::with_capacity(self.component_size(id));
+        let mut stack = vec![pos];
+        visited[self.index(pos)] = true;
+        while let Some(pos) = stack.pop() {
+            cells.push(pos);
+            for n in self.neighbors(pos) {
+                let i = self.index(n);
+                if !visited[i] && self.labels[i] == id {
+                    visited[i] = true;
+                    stack.push(n);
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn insert(&mut self, pos: (usize, usize)) {
+        if self.labels[self.index(pos)] != NO_COMPONENT {
+            return;
+        }
+
+        let mut neighbor_ids: Vec<(ComponentId, (usize, usize))> = Vec::with_capacity(4);
+        for n in self.neighbors(pos) {
+            let id = self.labels[self.index(n)];
+            if id != NO_COMPONENT && neighbor_ids.iter().all(|&(other, _)| other != id) {
+                neighbor_ids.push((id, n));
+            }
+        }
+
+        // Join the largest neighboring group and fold the others into it
+        let target = neighbor_ids
+            .iter()
+            .map(|&(id, _)| id)
+            .max_by_key(|&id| self.component_size(id))
+            .unwrap_or_else(|| self.fresh_id());
+
+        let i = self.index(pos);
+        self.labels[i] = target;
+        *self.sizes.entry(target).or_insert(0) += 1;
+
+        for (id, n) in neighbor_ids {
+            if id != target {
+                let moved = self.relabel(n, id, target);
+                self.sizes.remove(&id);
+                *self.sizes.entry(target).or_insert(0) += moved;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, pos: (usize, usize)) {
+        let id = self.labels[self.index(pos)];
+        if id == NO_COMPONENT {
+            return;
+        }
+        let i = self.index(pos);
+        self.labels[i] = NO_COMPONENT;
+        self.sizes.remove(&id);
+
+        // Removing a cell may split its group, so give each remaining piece its own id
+        for n in self.neighbors(pos) {
+            if self.labels[self.index(n)] == id {
+                let new_id = self.fresh_id();
+                let size = self.relabel(n, id, new_id);
+                self.sizes.insert(new_id, size);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Kind, World, GRID_WIDTH};
+
+    #[test]
+    fn structures_join_across_a_wrapped_seam() {
+        let mut world = World::new();
+        world.structural_integrity = false;
+        world.boundary = Boundary::Wrap;
+        let (last, y) = (GRID_WIDTH as usize - 1, 100);
+        for x in [last - 1, last, 0, 1] {
+            world.set_pixel((x, y), Kind::Stone);
+        }
+        world.update();
+        assert_eq!(world.components.component_cells((0, y)).len(), 4);
+
+        world.boundary = Boundary::Wall;
+        world.update();
+        assert_eq!(world.components.component_cells((0, y)).len(), 2);
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use basic_pixels::{Boundary, Edge, Kind};
+
+pub enum Command {
+    Replace {
+        from: Kind,
+        to: Kind,
+    },
+    Seed {
+        seed: u64,
+    },
+    // Holds an edge of the grid at a temperature, or lets it go again
+    Edge {
+        edge: Edge,
+        temperature: Option<i16>,
+    },
+    // Changes what happens to particles at the edges of the grid
+    Boundary {
+        boundary: Boundary,
+    },
+    // How many ticks the simulation runs per second at normal speed
+    TickRate {
+        rate: u32,
+    },
+    Save {
+        path: String,
+    },
+    // Compares two saved worlds, or a saved world against the live one
+    Diff {
+        first: String,
+        second: Option<String>,
+    },
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["replace", from, to] => Ok(Self::Replace {
+                from: from.parse()?,
+                to: to.parse()?,
+            }),
+            ["replace", ..] => Err("usage: replace <from> <to>".to_string()),
+            ["seed", seed] => Ok(Self::Seed {
+                seed: seed
+                    .parse()
+                    .map_err(|_| format!("invalid seed `{}`", seed))?,
+            }),
+            ["seed", ..] => Err("usage: seed <seed>".to_string()),
+            ["edge", edge, "off"] => Ok(Self::Edge {
+                edge: edge.parse()?,
+                temperature: None,
+            }),
+            ["edge", edge, temperature] => Ok(Self::Edge {
+                edge: edge.parse()?,
+                temperature: Some(
+                    temperature
+                        .parse()
+                        .map_err(|_| format!("invalid temperature `{}`", temperature))?,
+                ),
+            }),
+            ["edge", ..] => {
+                Err("usage: edge <top|bottom|left|right> <temperature|off>".to_string())
+            }
+            ["boundary", boundary] => Ok(Self::Boundary {
+                boundary: boundary.parse()?,
+            }),
+            ["boundary", ..] => Err("usage: boundary <wall|void|wrap>".to_string()),
+            ["tickrate", rate] => match rate.parse() {
+                Ok(rate) if rate > 0 => Ok(Self::TickRate { rate }),
+                _ => Err(format!("invalid tick rate `{}`", rate)),
+            },
+            ["tickrate", ..] => Err("usage: tickrate <ticks per second>".to_string()),
+            ["save", path] => Ok(Self::Save {
+                path: path.to_string(),
+            }),
+            ["save", ..] => Err("usage: save <path>".to_string()),
+            ["diff", first] => Ok(Self::Diff {
+                first: first.to_string(),
+                second: None,
+            }),
+            ["diff", first, second] => Ok(Self::Diff {
+                first: first.to_string(),
+                second: Some(second.to_string()),
+            }),
+            ["diff", ..] => Err("usage: diff <path> [<path>]".to_string()),
+            [name, ..] => Err(format!("unknown command `{}`", name)),
+            [] => Err("empty command".to_string()),
+        }
+    }
+}
+
+/// Commands typed into the terminal the simulation was started from
+pub struct Console {
+    lines: Receiver<String>,
+}
+
+impl Console {
+    pub fn spawn() -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { lines }
+    }
+
+    pub fn poll(&self) -> Option<Result<Command, String>> {
+        self.lines.try_recv().ok().map(|line| Command::parse(&line))
+    }
+}
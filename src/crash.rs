@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use basic_pixels::{save, World};
+
+const DUMP_DIR: &str = "crash-dumps";
+const INPUT_LOG_LEN: usize = 512;
+
+static PANIC_MESSAGE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keeps hold of the message of any panic so it can go into the crash dump,
+/// on top of printing it as usual
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Ok(mut message) = PANIC_MESSAGE.lock() {
+            *message = Some(info.to_string());
+        }
+        default_hook(info);
+    }));
+}
+
+/// The most recent input events, for retracing what led up to a crash
+pub struct InputLog {
+    entries: VecDeque<String>,
+}
+
+impl InputLog {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(INPUT_LOG_LEN),
+        }
+    }
+
+    pub fn record(&mut self, tick: u32, event: impl Debug) {
+        if self.entries.len() == INPUT_LOG_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(format!("{} {:?}", tick, event));
+    }
+}
+
+/// Writes the world, the panic message and the input log to a fresh
+/// directory, returning where it went
+pub fn write_dump(world: &World, input_log: &InputLog) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let dir = Path::new(DUMP_DIR).join(timestamp.to_string());
+    fs::create_dir_all(&dir)?;
+    save::write(world, &dir.join("world.bin"))?;
+
+    let message = PANIC_MESSAGE
+        .lock()
+        .ok()
+        .and_then(|message| message.clone())
+        .unwrap_or_default();
+    let mut report = format!(
+        "{}\nseed {}\ntick {}\n\nrecent input:\n",
+        message,
+        world.seed(),
+        world.tick()
+    );
+    for entry in &input_log.entries {
+        report.push_str(entry);
+        report.push('\n');
+    }
+    fs::write(dir.join("report.txt"), report)?;
+
+    Ok(dir)
+}
+
+/// Rebuilds the world saved in a crash dump
+pub fn load_dump(dir: &Path) -> io::Result<World> {
+    save::read(&dir.join("world.bin"))
+}
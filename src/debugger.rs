@@ -0,0 +1,80 @@
+use rand::RngCore;
+use rand_chacha::ChaCha12Rng;
+
+use crate::World;
+
+/// The world's random number generator, able to note down every number drawn
+/// from it while a watched cell is being updated
+pub struct TracedRng {
+    rng: ChaCha12Rng,
+    draws: Option<Vec<u64>>,
+}
+
+impl TracedRng {
+    pub fn new(rng: ChaCha12Rng) -> Self {
+        Self { rng, draws: None }
+    }
+
+    pub fn into_inner(self) -> ChaCha12Rng {
+        self.rng
+    }
+
+    /// Starts or stops noting down draws, returning whatever was noted down
+    /// since the last call
+    pub fn trace(&mut self, on: bool) -> Option<Vec<u64>> {
+        std::mem::replace(&mut self.draws, on.then(Vec::new))
+    }
+
+    fn note(&mut self, value: u64) {
+        if let Some(draws) = &mut self.draws {
+            draws.push(value);
+        }
+    }
+}
+
+impl RngCore for TracedRng {
+    fn next_u32(&mut self) -> u32 {
+        let value = self.rng.next_u32();
+        self.note(value as u64);
+        value
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let value = self.rng.next_u64();
+        self.note(value);
+        value
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// The kinds in the 3x3 block around a cell, one row per line
+pub fn neighborhood(world: &World, (x, y): (usize, usize)) -> String {
+    let mut rows = Vec::with_capacity(3);
+    for ny in y as i32 - 1..=y as i32 + 1 {
+        let row: Vec<String> = (x as i32 - 1..=x as i32 + 1)
+            .map(|nx| {
+                if nx < 0 || nx >= world.width() as i32 || ny < 0 || ny >= world.height() as i32 {
+                    format!("{:<12}", "-")
+                } else {
+                    format!(
+                        "{:<12}",
+                        world
+                            .particles
+                            .get((nx as usize, ny as usize))
+                            .kind()
+                            .name()
+                    )
+                }
+            })
+            .collect();
+        rows.push(format!("  {}", row.join("").trim_end()));
+    }
+    rows.join("\n")
+}
@@ -0,0 +1,1026 @@
+use rand::Rng;
+
+use crate::debugger::TracedRng;
+use crate::{
+    Edge, Kind, Particle, World, AMBIENT_TEMPERATURE, BATTERY_INTERVAL, BOILING_POINT,
+    CEMENT_SET_TICKS, CONDUCTOR_COOLDOWN, CRYSTAL_GROW_ODDS, CRYSTAL_SALT_GROW_ODDS, CRYSTAL_SIZE,
+    DRY_ICE_HEATED_SUBLIMATE_ODDS, DRY_ICE_SUBLIMATE_ODDS, FIREWORK_COLORS, FUSE_BURN_TICKS,
+    FUSE_SPREAD_TICKS, METEOR_BLAST_POWER, METEOR_BLAST_RADIUS, METEOR_SPEED, NITRO_BLAST_POWER,
+    NITRO_BLAST_RADIUS, NITRO_FALL_LIMIT, NITRO_IMPACT_SPEED, PLANT_HEIGHT, SAND_COMPACT_DEPTH,
+    SAND_COMPACT_ODDS, SEED_GERMINATE_TICKS, SNOW_CRUSH_DEPTH, SNOW_MELT_TICKS, SOAP_BUBBLES,
+    SOAP_BUBBLE_ODDS, SPARK_LIFE, SPONGE_BURN_ODDS, SPONGE_CAPACITY, STEAM_CONDENSE_ODDS,
+    THERMITE_BURN_TICKS, THERMITE_MELT_ODDS, URANIUM_FLICKER_ODDS, URANIUM_GLOW_LEVELS,
+    WATER_EVAPORATE_ODDS, WATER_EVAPORATING_POINT, WET_SAND_DRY_ODDS, WICK_BURN_RATE,
+    WICK_BURN_TICKS,
+};
+
+/// The rules a kind of particle follows on its turn each tick, once the
+/// steps every kind shares (burning, reacting, being blown about...) are done
+pub trait Element {
+    fn update(&self, ctx: &mut CellCtx);
+}
+
+/// The cell whose turn it is, along with the world it's in
+pub struct CellCtx<'a> {
+    pub world: &'a mut World,
+    pub pos: (usize, usize),
+    pub rng: &'a mut TracedRng,
+}
+
+impl CellCtx<'_> {
+    fn parts(&mut self) -> ((usize, usize), &mut World, &mut TracedRng) {
+        (self.pos, self.world, self.rng)
+    }
+}
+
+/// Kinds that just sit there, or whose rules all live in the shared steps
+pub struct Inert;
+
+impl Element for Inert {
+    fn update(&self, _ctx: &mut CellCtx) {}
+}
+
+pub struct WaterSource;
+
+impl Element for WaterSource {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, _) = ctx.parts();
+        if let Some(below) = world
+            .offset((x, y), (0, 1))
+            .filter(|&below| world.particles.get(below).empty())
+        {
+            world.set_pixel(below, Kind::Water);
+        }
+    }
+}
+
+pub struct Battery;
+
+impl Element for Battery {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, _) = ctx.parts();
+        // A battery's counter is how long it has been charging for
+        if world.particles.get((x, y)).counter < BATTERY_INTERVAL {
+            world.modify((x, y), |p| p.counter += 1);
+            return;
+        }
+        world.modify((x, y), |p| p.counter = 0);
+        for (x_off, y_off) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+            let Some(pos) = world.offset((x, y), (x_off, y_off)) else {
+                continue;
+            };
+            let neighbor = world.particles.get(pos);
+            if neighbor.kind().conducts() && neighbor.counter == 0 {
+                world.replace(
+                    pos,
+                    Particle::new(Kind::Spark)
+                        .with_turn(world.turn())
+                        .with_counter(SPARK_LIFE)
+                        .with_stored(neighbor.kind())
+                        .with_temperature(neighbor.temperature),
+                );
+            }
+        }
+    }
+}
+
+/// Piles up, slipping off the sides of heaps and splashing into liquids
+pub struct Powder;
+
+impl Element for Powder {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        if world.stuck((x, y)) {
+            return;
+        }
+
+        let Some(below) = world.offset((x, y), (0, 1)) else {
+            world.fall_off((x, y));
+            return;
+        };
+        // Landing hard on nitro sets it off
+        let particle = world.particles.get((x, y));
+        if world.particles.get(below).kind() == Kind::Nitro
+            && particle.velocity.1 >= NITRO_IMPACT_SPEED
+        {
+            let below = (below.0 as i32, below.1 as i32);
+            world.explode(below, NITRO_BLAST_RADIUS, NITRO_BLAST_POWER, rng);
+        } else if world.fall((x, y)).is_none() && !world.splash((x, y), rng) {
+            world.slip((x, y), rng);
+        }
+    }
+}
+
+pub struct Seed;
+
+impl Element for Seed {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Seeds resting on wet ground germinate after a while
+        if world.on_wet_ground((x, y)) {
+            world.modify((x, y), |seed| {
+                seed.counter += 1;
+                if seed.counter >= SEED_GERMINATE_TICKS {
+                    seed.set_kind(Kind::Plant);
+                    seed.counter = rng.gen_range(PLANT_HEIGHT);
+                }
+            });
+            return;
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+pub struct Sand;
+
+impl Element for Sand {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Sand buried deep enough for long enough is pressed into sandstone
+        if rng.gen_ratio(1, SAND_COMPACT_ODDS) {
+            let depth = (0..y)
+                .rev()
+                .take(SAND_COMPACT_DEPTH)
+                .take_while(|&above_y| !world.particles.get((x, above_y)).empty())
+                .count();
+            if depth == SAND_COMPACT_DEPTH {
+                world.replace(
+                    (x, y),
+                    Particle::new(Kind::Sandstone).with_turn(world.turn()),
+                );
+                return;
+            }
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+pub struct DryIce;
+
+impl Element for DryIce {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Dry ice slowly turns into carbon dioxide, quicker when it's warm
+        let odds = if world.find_neighbor((x, y), |p| p.kind().is_hot()).is_some() {
+            DRY_ICE_HEATED_SUBLIMATE_ODDS
+        } else {
+            DRY_ICE_SUBLIMATE_ODDS
+        };
+        if rng.gen_ratio(1, odds) {
+            world.modify((x, y), |p| p.set_kind(Kind::Co2));
+            return;
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+pub struct Thermite;
+
+impl Element for Thermite {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Lit thermite burns hot enough to melt its way down through metal
+        // and stone, leaving lava behind. Its counter is how long it has
+        // been burning for.
+        let thermite = world.particles.get((x, y));
+        if !thermite.burning() {
+            if world
+                .find_neighbor((x, y), |p| p.kind().is_hot() || p.kind() == Kind::Spark)
+                .is_some()
+            {
+                world.modify((x, y), |p| p.set_burning(true));
+            }
+        } else if thermite.counter >= THERMITE_BURN_TICKS {
+            world.replace((x, y), Particle::new(Kind::Fire).with_turn(world.turn()));
+            return;
+        } else {
+            world.modify((x, y), |p| p.counter += 1);
+
+            // Mostly straight down, sometimes off to one side
+            let melt_x = if rng.gen_ratio(1, 4) {
+                rng.gen::<bool>() as i32 * 2 - 1
+            } else {
+                0
+            };
+            if let Some(below) = world
+                .offset((x, y), (melt_x, 1))
+                .filter(|&below| {
+                    matches!(world.particles.get(below).kind(), Kind::Metal | Kind::Stone)
+                })
+                .filter(|_| rng.gen_ratio(1, THERMITE_MELT_ODDS))
+            {
+                world.replace(
+                    below,
+                    Particle::new(Kind::Lava)
+                        .with_turn(world.turn())
+                        .with_temperature(Kind::Lava.painted_temperature()),
+                );
+                world.swap((x, y), below);
+                return;
+            }
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+pub struct Soap;
+
+impl Element for Soap {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Soap in water blows bubbles out of the water around it until
+        // it's used up. Its counter is how many it has blown so far.
+        if rng.gen_ratio(1, SOAP_BUBBLE_ODDS) {
+            if let Some(pos) = world.find_neighbor((x, y), |p| p.kind() == Kind::Water) {
+                world.replace(pos, Particle::new(Kind::Bubble).with_turn(world.turn()));
+                if world.particles.get((x, y)).counter + 1 >= SOAP_BUBBLES {
+                    world.replace((x, y), Particle::default());
+                    return;
+                }
+                world.modify((x, y), |p| p.counter += 1);
+            }
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+pub struct Uranium;
+
+impl Element for Uranium {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Uranium's counter is how brightly it's glowing right now
+        if rng.gen_ratio(1, URANIUM_FLICKER_ODDS) {
+            let glow = rng.gen_range(0..URANIUM_GLOW_LEVELS);
+            world.modify((x, y), |p| p.counter = glow);
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+pub struct Firework;
+
+impl Element for Firework {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // A lit firework shoots straight up, bursting once it's flown
+        // for long enough or hits something. Its counter is how long
+        // it has been flying for.
+        let firework = world.particles.get((x, y));
+        if firework.counter == 0 {
+            if world
+                .find_neighbor((x, y), |p| p.kind().is_hot() || p.kind() == Kind::Spark)
+                .is_some()
+            {
+                let color = FIREWORK_COLORS[rng.gen_range(0..FIREWORK_COLORS.len())];
+                world.modify((x, y), |p| {
+                    p.counter = 1;
+                    p.set_stored(color);
+                });
+            }
+        } else {
+            world.launch_firework((x, y), rng);
+            return;
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+pub struct WetSand;
+
+impl Element for WetSand {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Wet sand clumps together into steep piles like gravel's,
+        // until it dries out
+        if rng.gen_ratio(1, WET_SAND_DRY_ODDS)
+            && world
+                .find_neighbor((x, y), |p| p.kind().is_liquid())
+                .is_none()
+        {
+            world.modify((x, y), |p| p.set_kind(Kind::Sand));
+            return;
+        }
+
+        Powder.update(ctx);
+    }
+}
+
+/// Falls, splashes, and otherwise spreads out sideways to find its level
+pub struct Liquid;
+
+impl Element for Liquid {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        if world.fall((x, y)).is_none() && !world.splash((x, y), rng) {
+            world.spread_liquid((x, y), rng);
+        }
+    }
+}
+
+pub struct Water;
+
+impl Element for Water {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Open water slowly evaporates into the air above it while it's warm
+        let warm = world.particles.get((x, y)).temperature >= WATER_EVAPORATING_POINT
+            || world.find_neighbor((x, y), |p| p.kind().is_hot()).is_some();
+        if warm
+            && world
+                .offset((x, y), (0, -1))
+                .is_some_and(|above| world.particles.get(above).empty())
+            && rng.gen_ratio(1, WATER_EVAPORATE_ODDS)
+        {
+            world.modify((x, y), |p| p.set_kind(Kind::Steam));
+            return;
+        }
+
+        Liquid.update(ctx);
+    }
+}
+
+pub struct Saltwater;
+
+impl Element for Saltwater {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Evaporating saltwater leaves its salt behind
+        if rng.gen_ratio(1, 10) && world.find_neighbor((x, y), |p| p.kind().is_hot()).is_some() {
+            world.modify((x, y), |p| p.set_kind(Kind::Salt));
+            return;
+        }
+
+        Liquid.update(ctx);
+    }
+}
+
+pub struct Nitro;
+
+impl Element for Nitro {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Counts how far it has fallen, and goes off if it lands hard
+        if let Some(landed) = world.fall((x, y)) {
+            world.modify(landed, |p| {
+                p.counter = p.counter.saturating_add((landed.1 - y) as u8)
+            });
+        } else if world.particles.get((x, y)).counter > 0 {
+            if world.particles.get((x, y)).counter >= NITRO_FALL_LIMIT {
+                world.explode(
+                    (x as i32, y as i32),
+                    NITRO_BLAST_RADIUS,
+                    NITRO_BLAST_POWER,
+                    rng,
+                );
+            } else {
+                world.modify((x, y), |p| p.counter = 0);
+            }
+        } else if !world.splash((x, y), rng) {
+            world.spread_liquid((x, y), rng);
+        }
+    }
+}
+
+pub struct Snow;
+
+impl Element for Snow {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        if rng.gen_ratio(1, 20) && world.find_neighbor((x, y), |p| p.kind().is_hot()).is_some() {
+            world.modify((x, y), |snow| {
+                snow.set_kind(Kind::Water);
+                snow.counter = 0;
+            });
+            return;
+        }
+
+        let weight = (0..y)
+            .rev()
+            .take(SNOW_CRUSH_DEPTH)
+            .take_while(|&above_y| !world.particles.get((x, above_y)).empty())
+            .count();
+        if weight == SNOW_CRUSH_DEPTH {
+            world.modify((x, y), |snow| {
+                snow.counter += 1;
+                if snow.counter >= SNOW_MELT_TICKS {
+                    snow.set_kind(Kind::Water);
+                    snow.counter = 0;
+                }
+            });
+            return;
+        }
+
+        // Snow is light, so it drifts down slower than sand
+        // and floats on top of liquids
+        if world.offset((x, y), (0, 1)).is_some() && rng.gen() && !world.stuck((x, y)) {
+            if world.particles.get((x, y + 1)).empty() {
+                world.move_particle((x, y), (x, y + 1));
+            } else {
+                world.slip((x, y), rng);
+            }
+        }
+    }
+}
+
+pub struct Mud;
+
+impl Element for Mud {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Mud dries back into sand once there's no water around to keep it wet
+        if rng.gen_ratio(1, 300)
+            && world
+                .find_neighbor((x, y), |p| p.kind().is_liquid())
+                .is_none()
+        {
+            world.modify((x, y), |p| p.set_kind(Kind::Sand));
+            return;
+        }
+
+        world.flow_viscous((x, y), rng);
+    }
+}
+
+pub struct Plant;
+
+impl Element for Plant {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // The tip of a stalk keeps growing upwards until it runs out
+        let plant = world.particles.get((x, y));
+        if plant.counter > 0
+            && world
+                .offset((x, y), (0, -1))
+                .is_some_and(|above| world.particles.get(above).empty())
+            && rng.gen_ratio(Kind::Plant.update_stride(), 20)
+        {
+            world.replace((x, y - 1), plant.with_counter(plant.counter - 1));
+            world.modify((x, y), |p| p.counter = 0);
+        }
+    }
+}
+
+pub struct Virus;
+
+impl Element for Virus {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // With nothing left to feed on, it dies off
+        if world.find_neighbor((x, y), |p| !p.empty()).is_none() {
+            if rng.gen_ratio(1, 20) {
+                world.replace((x, y), Particle::default());
+            }
+            return;
+        }
+
+        let offset = (rng.gen_range(-1..=1), rng.gen_range(-1..=1));
+        if let Some(pos) = world
+            .offset((x, y), offset)
+            .filter(|_| rng.gen_ratio(1, 20))
+        {
+            let victim = world.particles.get(pos).kind();
+            if !matches!(victim, Kind::Empty | Kind::Virus | Kind::Void) {
+                world.replace(pos, Particle::new(Kind::Virus).with_turn(world.turn()));
+            }
+        }
+    }
+}
+
+pub struct Gas;
+
+impl Element for Gas {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        if world
+            .find_neighbor((x, y), |p| p.kind().is_hot() || p.kind() == Kind::Spark)
+            .is_some()
+        {
+            world.ignite_pocket((x, y));
+            return;
+        }
+
+        world.drift_gas((x, y), rng);
+    }
+}
+
+pub struct Steam;
+
+impl Element for Steam {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Gathers under anything cool overhead and drips back down as water
+        let ceiling = match world.offset((x, y), (0, -1)) {
+            Some(above) => {
+                let above = world.particles.get(above);
+                (!above.empty() && !above.kind().is_gas()).then_some(above.temperature)
+            }
+            None => {
+                Some(world.edge_temperatures[Edge::Top as usize].unwrap_or(AMBIENT_TEMPERATURE))
+            }
+        };
+        if ceiling.is_some_and(|temperature| temperature < BOILING_POINT)
+            && rng.gen_ratio(1, STEAM_CONDENSE_ODDS)
+        {
+            world.modify((x, y), |p| p.set_kind(Kind::Water));
+            return;
+        }
+
+        world.drift_gas((x, y), rng);
+    }
+}
+
+/// Wanders around, mostly rising
+pub struct Drifting;
+
+impl Element for Drifting {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        world.drift_gas((x, y), rng);
+    }
+}
+
+pub struct Helium;
+
+impl Element for Helium {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        world.rise_helium((x, y), rng);
+    }
+}
+
+pub struct Glue;
+
+impl Element for Glue {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Sets together with any powder it touches, which it remembers
+        // so the result takes on its color
+        if let Some(pos) = world.find_neighbor((x, y), |p| p.kind().is_powder()) {
+            let composite = Particle::new(Kind::Composite)
+                .with_turn(world.turn())
+                .with_stored(world.particles.get(pos).kind());
+            world.replace(pos, composite);
+            world.replace((x, y), composite);
+            return;
+        }
+
+        world.flow_viscous((x, y), rng);
+    }
+}
+
+/// Oozes slowly downhill
+pub struct Viscous;
+
+impl Element for Viscous {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        world.flow_viscous((x, y), rng);
+    }
+}
+
+pub struct Cement;
+
+impl Element for Cement {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // A cement particle's counter is how long it has gone without moving
+        if let Some(moved_to) = world.flow_viscous((x, y), rng) {
+            world.modify(moved_to, |p| p.counter = 0);
+        } else if world.particles.get((x, y)).counter >= CEMENT_SET_TICKS {
+            world.replace((x, y), Particle::new(Kind::Stone).with_turn(world.turn()));
+        } else {
+            world.modify((x, y), |p| p.counter += 1);
+        }
+    }
+}
+
+pub struct Wax;
+
+impl Element for Wax {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        if rng.gen_ratio(Kind::Wax.update_stride(), 10)
+            && world.find_neighbor((x, y), |p| p.kind().is_hot()).is_some()
+        {
+            world.modify((x, y), |p| p.set_kind(Kind::MoltenWax));
+        }
+    }
+}
+
+pub struct Wick;
+
+impl Element for Wick {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // A wick's counter is how long it has been burning for. Only the
+        // top burns, handing the flame down once it's gone.
+        let wick = world.particles.get((x, y));
+        let burned = wick.counter;
+        if !wick.burning() {
+            if world.find_neighbor((x, y), |p| p.kind().is_hot()).is_some() {
+                world.modify((x, y), |p| p.set_burning(true));
+            }
+        } else if burned >= WICK_BURN_TICKS {
+            world.replace((x, y), Particle::default());
+            if let Some(below) = world
+                .offset((x, y), (0, 1))
+                .filter(|&below| world.particles.get(below).kind() == Kind::Wick)
+            {
+                world.modify(below, |p| p.set_burning(true));
+            }
+        } else {
+            if rng.gen_ratio(1, WICK_BURN_RATE) {
+                world.modify((x, y), |p| p.counter += 1);
+            }
+            if let Some(above) = world
+                .offset((x, y), (0, -1))
+                .filter(|&above| world.particles.get(above).empty())
+            {
+                world.replace(above, Particle::new(Kind::Fire).with_turn(world.turn()));
+            }
+        }
+    }
+}
+
+pub struct MoltenWax;
+
+impl Element for MoltenWax {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Hardens again wherever it ends up once the heat is gone
+        if rng.gen_ratio(1, 40) && world.find_neighbor((x, y), |p| p.kind().is_hot()).is_none() {
+            world.modify((x, y), |p| p.set_kind(Kind::Wax));
+            return;
+        }
+
+        world.flow_viscous((x, y), rng);
+    }
+}
+
+pub struct Spark;
+
+impl Element for Spark {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, _) = ctx.parts();
+        let spark = world.particles.get((x, y));
+        if spark.counter == SPARK_LIFE {
+            for (x_off, y_off) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                // Conductors have already been charged all the way along
+                let Some(pos) = world.offset((x, y), (x_off, y_off)) else {
+                    continue;
+                };
+                let neighbor = world.particles.get((pos.0, pos.1));
+                if neighbor.kind().flammability() > 0 && !neighbor.wet() {
+                    world.ignite(pos);
+                }
+            }
+        }
+
+        if spark.counter <= 1 {
+            // Hand the cell back to whatever the spark was passing through
+            let cooldown = if spark.stored().conducts() {
+                CONDUCTOR_COOLDOWN
+            } else {
+                0
+            };
+            world.replace(
+                (x, y),
+                Particle::new(spark.stored())
+                    .with_turn(spark.turn())
+                    .with_counter(cooldown)
+                    .with_stored(Kind::Empty)
+                    .with_temperature(spark.temperature),
+            );
+        } else {
+            world.modify((x, y), |p| p.counter -= 1);
+        }
+    }
+}
+
+pub struct Meteor;
+
+impl Element for Meteor {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        let meteor = world.particles.get((x, y));
+        let drift = if meteor.counter == 0 { -1 } else { 1 };
+
+        let (mut mx, mut my) = (x, y);
+        let mut impact = None;
+        for _ in 0..METEOR_SPEED {
+            let dx = if rng.gen_ratio(1, 3) { drift } else { 0 };
+            let Some((nx, ny)) = world
+                .offset((mx, my), (dx, 1))
+                .or_else(|| world.offset((mx, my), (0, 1)))
+            else {
+                impact = Some((mx as i32, my as i32));
+                break;
+            };
+            if !world.particles.get((nx, ny)).empty() {
+                impact = Some((nx as i32, ny as i32));
+                break;
+            }
+            (mx, my) = (nx, ny);
+        }
+
+        // Streak down, leaving a trail of flame
+        world.move_particle((x, y), (mx, my));
+        if (mx, my) != (x, y) && rng.gen() {
+            world.replace((x, y), Particle::new(Kind::Fire).with_turn(meteor.turn()));
+        }
+
+        if let Some(center) = impact {
+            world.explode(center, METEOR_BLAST_RADIUS, METEOR_BLAST_POWER, rng);
+        }
+    }
+}
+
+pub struct Clone;
+
+impl Element for Clone {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        let stored = world.particles.get((x, y)).stored();
+        if stored == Kind::Empty {
+            // Remember the first thing that touches us
+            if let Some((nx, ny)) =
+                world.find_neighbor((x, y), |p| !p.empty() && p.kind() != Kind::Clone)
+            {
+                let stored = world.particles.get((nx, ny)).kind();
+                world.modify((x, y), |p| p.set_stored(stored));
+            }
+        } else {
+            let offset = (rng.gen_range(-1..=1), rng.gen_range(-1..=1));
+            if let Some(pos) = world
+                .offset((x, y), offset)
+                .filter(|&pos| world.particles.get(pos).empty())
+            {
+                world.set_pixel(pos, stored);
+            }
+        }
+    }
+}
+
+pub struct Crystal;
+
+impl Element for Crystal {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Crystals grow diagonally into the water around them, so they
+        // form lattices rather than blobs. Saltwater feeds them much
+        // faster, using up its salt. A crystal's counter is how far
+        // it is from the seed it grew out of.
+        let crystal = world.particles.get((x, y));
+        if crystal.counter >= CRYSTAL_SIZE {
+            return;
+        }
+        let offset = (
+            rng.gen::<bool>() as i32 * 2 - 1,
+            rng.gen::<bool>() as i32 * 2 - 1,
+        );
+        let Some(pos) = world.offset((x, y), offset) else {
+            return;
+        };
+        let odds = match world.particles.get(pos).kind() {
+            Kind::Water => CRYSTAL_GROW_ODDS,
+            Kind::Saltwater => CRYSTAL_SALT_GROW_ODDS,
+            _ => return,
+        };
+        // Only grow out into open water, so branches stay thin
+        if rng.gen_ratio(1, odds) && world.count_neighbors(pos, |p| p.kind() == Kind::Crystal) == 1
+        {
+            world.replace(
+                pos,
+                Particle::new(Kind::Crystal)
+                    .with_turn(world.turn())
+                    .with_counter(crystal.counter + 1),
+            );
+        }
+    }
+}
+
+pub struct Sponge;
+
+impl Element for Sponge {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // A sponge's counter is how much water it's holding
+        if rng.gen_ratio(1, SPONGE_BURN_ODDS)
+            && world.find_neighbor((x, y), |p| p.kind().is_hot()).is_some()
+        {
+            world.wring_sponge((x, y));
+            world.replace((x, y), Particle::new(Kind::Fire).with_turn(world.turn()));
+            return;
+        }
+
+        if world.particles.get((x, y)).counter < SPONGE_CAPACITY {
+            if let Some(pos) = world.find_neighbor((x, y), |p| p.kind() == Kind::Water) {
+                world.replace(pos, Particle::default());
+                world.modify((x, y), |p| p.counter += 1);
+            }
+        }
+    }
+}
+
+pub struct Drain;
+
+impl Element for Drain {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, _) = ctx.parts();
+        while let Some(pos) = world.find_neighbor((x, y), |p| p.kind().is_liquid()) {
+            world.replace(pos, Particle::default());
+        }
+    }
+}
+
+pub struct Void;
+
+impl Element for Void {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, _) = ctx.parts();
+        while let Some(pos) = world.find_neighbor((x, y), |p| !p.empty() && p.kind() != Kind::Void)
+        {
+            world.replace(pos, Particle::default());
+        }
+    }
+}
+
+pub struct Fuse;
+
+impl Element for Fuse {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, _) = ctx.parts();
+        // A fuse's counter is how long it has been burning for
+        let fuse = world.particles.get((x, y));
+        let burned = fuse.counter;
+        if !fuse.burning() {
+            if world
+                .find_neighbor((x, y), |p| p.kind().is_hot() || p.kind() == Kind::Spark)
+                .is_some()
+            {
+                world.modify((x, y), |p| p.set_burning(true));
+            }
+        } else if burned >= FUSE_BURN_TICKS {
+            world.replace((x, y), Particle::new(Kind::Fire).with_turn(world.turn()));
+        } else {
+            if burned == FUSE_SPREAD_TICKS {
+                for y_off in -1..=1 {
+                    for x_off in -1..=1 {
+                        let Some(pos) = world.offset((x, y), (x_off, y_off)) else {
+                            continue;
+                        };
+                        world.modify(pos, |neighbor| {
+                            if neighbor.kind() == Kind::Fuse && !neighbor.burning() {
+                                neighbor.set_burning(true);
+                            }
+                        });
+                    }
+                }
+            }
+            world.modify((x, y), |p| p.counter += 1);
+        }
+    }
+}
+
+pub struct Bubble;
+
+impl Element for Bubble {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // A bubble is blown out of water and pops back into it, sooner
+        // once it has reached the surface
+        let surfaced = world
+            .offset((x, y), (0, -1))
+            .is_none_or(|above| !world.particles.get(above).kind().is_liquid());
+        if surfaced && rng.gen_ratio(1, 8) {
+            world.replace((x, y), Particle::new(Kind::Water).with_turn(world.turn()));
+            return;
+        }
+
+        // Rise through the water, wobbling from side to side
+        if !surfaced {
+            let up = world
+                .offset((x, y), (rng.gen_range(-1..=1), -1))
+                .filter(|&up| world.particles.get(up).kind().is_liquid())
+                .unwrap_or((x, y - 1));
+            world.swap((x, y), up);
+        }
+    }
+}
+
+pub struct Ant;
+
+impl Element for Ant {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        world.walk_ant((x, y), rng);
+    }
+}
+
+pub struct Ember;
+
+impl Element for Ember {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        // Drift down slowly, wandering from side to side
+        if rng.gen() {
+            if let Some(down) = world
+                .offset((x, y), (rng.gen_range(-1..=1), 1))
+                .filter(|&down| world.particles.get(down).empty())
+            {
+                world.move_particle((x, y), down);
+            }
+        }
+    }
+}
+
+pub struct Fire;
+
+impl Element for Fire {
+    fn update(&self, ctx: &mut CellCtx) {
+        let ((x, y), world, rng) = ctx.parts();
+        if let Some(up) = world
+            .offset((x, y), (rng.gen_range(-1..=1), -1))
+            .filter(|&up| world.particles.get(up).empty())
+        {
+            world.move_particle((x, y), up);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::count_kind;
+    use crate::{GRID_HEIGHT, WATER_EVAPORATING_POINT};
+
+    #[test]
+    fn nitro_only_goes_off_when_hit_hard() {
+        let pool = || {
+            let mut world = World::new();
+            let floor = GRID_HEIGHT as usize - 1;
+            for y in floor - 4..=floor {
+                world.set_pixel((99, y), Kind::Stone);
+                world.set_pixel((120, y), Kind::Stone);
+            }
+            for x in 100..120 {
+                world.set_pixel((x, floor), Kind::Nitro);
+            }
+            world
+        };
+
+        // Sand set down gently on the nitro just sinks into it
+        let mut world = pool();
+        world.set_pixel((110, GRID_HEIGHT as usize - 2), Kind::Sand);
+        for _ in 0..20 {
+            world.update();
+        }
+        assert_eq!(count_kind(&world, Kind::Nitro), 20);
+
+        // Sand dropped onto it from high up sets it off
+        let mut world = pool();
+        world.set_pixel((110, 100), Kind::Sand);
+        for _ in 0..100 {
+            world.update();
+        }
+        assert!(count_kind(&world, Kind::Nitro) < 20);
+    }
+
+    #[test]
+    fn only_warm_water_evaporates() {
+        let pool = |temperature: i16| {
+            let mut world = World::with_size((32, 32), 1);
+            for y in 24..32 {
+                for x in 0..32 {
+                    world.set_pixel((x, y), Kind::Water);
+                    world
+                        .particles
+                        .modify((x, y), |p| p.temperature = temperature);
+                }
+            }
+            world
+        };
+
+        let mut cold = pool(AMBIENT_TEMPERATURE);
+        for _ in 0..1000 {
+            cold.update();
+        }
+        assert_eq!(count_kind(&cold, Kind::Steam), 0);
+
+        // Held warm from below, so it doesn't cool off before it gets the chance
+        let mut warm = pool(WATER_EVAPORATING_POINT + 20);
+        warm.edge_temperatures[Edge::Bottom as usize] = Some(WATER_EVAPORATING_POINT + 20);
+        for _ in 0..1000 {
+            warm.update();
+        }
+        assert!(count_kind(&warm, Kind::Steam) > 0);
+    }
+}
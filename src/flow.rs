@@ -0,0 +1,45 @@
+use basic_pixels::Change;
+
+// How much brightness a streak loses each frame, and its color at full brightness
+const STREAK_FADE: u8 = 20;
+const STREAK_COLOR: [u8; 3] = [0x90, 0xE8, 0xFF];
+
+/// A render mode that leaves fading streaks wherever particles have been
+/// moving, to make currents visible
+pub struct FlowView {
+    width: usize,
+    trails: Vec<u8>,
+}
+
+impl FlowView {
+    pub fn new((width, height): (usize, usize)) -> Self {
+        Self {
+            width,
+            trails: vec![0; width * height],
+        }
+    }
+
+    /// Fades the existing streaks and lights up every cell that a particle
+    /// moved into or out of since the last frame
+    pub fn record(&mut self, changes: &[Change]) {
+        for trail in &mut self.trails {
+            *trail = trail.saturating_sub(STREAK_FADE);
+        }
+        for change in changes {
+            if change.old.kind() != change.new.kind() {
+                let (x, y) = change.pos;
+                self.trails[y * self.width + x] = u8::MAX;
+            }
+        }
+    }
+
+    /// Dims the world already drawn into `frame` and draws the streaks over it
+    pub fn draw(&self, frame: &mut [u8]) {
+        for (pixel, &trail) in frame.chunks_exact_mut(4).zip(&self.trails) {
+            for (channel, streak) in pixel.iter_mut().zip(STREAK_COLOR) {
+                let streak = streak as u16 * trail as u16 / u8::MAX as u16;
+                *channel = (*channel / 3).saturating_add(streak as u8);
+            }
+        }
+    }
+}
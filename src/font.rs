@@ -0,0 +1,93 @@
+use crate::{WIN_HEIGHT, WIN_WIDTH};
+
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+
+// Each glyph is five rows of three bits, most significant bit on the left
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
+
+/// How wide and tall the box `draw_text` draws a line of text on is
+pub fn text_size(text: &str) -> (usize, usize) {
+    (
+        text.chars().count() * (GLYPH_WIDTH + 1) + 1,
+        GLYPH_HEIGHT + 2,
+    )
+}
+
+/// Draws a line of text onto a dark backing box whose top-left corner is at
+/// `(x, y)` in the window, clipping anything that falls outside of it
+pub fn draw_text(frame: &mut [u8], (x, y): (usize, usize), text: &str) {
+    let chars: Vec<char> = text.chars().collect();
+    let (width, height) = text_size(text);
+    for row in 0..height {
+        for col in 0..width {
+            let (px, py) = (x + col, y + row);
+            if px >= WIN_WIDTH as usize || py >= WIN_HEIGHT as usize {
+                continue;
+            }
+
+            let lit = row >= 1 && col >= 1 && (col - 1) % (GLYPH_WIDTH + 1) < GLYPH_WIDTH && {
+                let c = chars
+                    .get((col - 1) / (GLYPH_WIDTH + 1))
+                    .copied()
+                    .unwrap_or(' ');
+                let bits = glyph(c).get(row - 1).copied().unwrap_or(0);
+                bits & (1 << (GLYPH_WIDTH - 1 - (col - 1) % (GLYPH_WIDTH + 1))) != 0
+            };
+
+            let i = (py * WIN_WIDTH as usize + px) * 4;
+            let rgba = if lit {
+                [0xFF, 0xFF, 0xFF, 0xFF]
+            } else {
+                [0x10, 0x10, 0x10, 0xFF]
+            };
+            frame[i..i + 4].copy_from_slice(&rgba);
+        }
+    }
+}
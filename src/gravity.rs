@@ -0,0 +1,98 @@
+use rand::Rng;
+
+use crate::ANTIGRAVITY_RADIUS;
+
+/// Which way things fall, unless an anti-gravity field turns them around
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    // A step to one of the four neighboring cells
+    Direction(i32, i32),
+    // Towards a point
+    Radial((usize, usize)),
+}
+
+impl Pull {
+    pub const DOWN: Pull = Pull::Direction(0, 1);
+
+    /// Which way is down at a cell, as a step to one of its four neighbors.
+    /// Radial gravity picks an axis at random, in proportion to how far away
+    /// the point is along each, so that on average it pulls straight at it.
+    pub fn down_at(&self, (x, y): (usize, usize), rng: &mut impl Rng) -> (i32, i32) {
+        match *self {
+            Pull::Direction(dx, dy) => (dx, dy),
+            Pull::Radial((cx, cy)) => {
+                let (dx, dy) = (cx as i32 - x as i32, cy as i32 - y as i32);
+                if (dx, dy) == (0, 0) {
+                    (0, 0)
+                } else if rng.gen_range(0..dx.abs() + dy.abs()) < dx.abs() {
+                    (dx.signum(), 0)
+                } else {
+                    (0, dy.signum())
+                }
+            }
+        }
+    }
+}
+
+/// How many anti-gravity emitters reach each cell. Gravity is inverted
+/// wherever at least one does.
+#[derive(Clone)]
+pub struct GravityField {
+    width: usize,
+    height: usize,
+    coverage: Vec<u16>,
+}
+
+impl GravityField {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            coverage: vec![0; width * height],
+        }
+    }
+
+    fn cells_in_range(&self, (cx, cy): (usize, usize)) -> impl Iterator<Item = usize> {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let (cx, cy) = (cx as i32, cy as i32);
+        (cy - ANTIGRAVITY_RADIUS..=cy + ANTIGRAVITY_RADIUS)
+            .flat_map(move |y| {
+                (cx - ANTIGRAVITY_RADIUS..=cx + ANTIGRAVITY_RADIUS).map(move |x| (x, y))
+            })
+            .filter(move |&(x, y)| {
+                x >= 0
+                    && x < width
+                    && y >= 0
+                    && y < height
+                    && (x - cx).pow(2) + (y - cy).pow(2) <= ANTIGRAVITY_RADIUS.pow(2)
+            })
+            .map(move |(x, y)| (y * width + x) as usize)
+    }
+
+    pub fn add_emitter(&mut self, pos: (usize, usize)) {
+        for i in self.cells_in_range(pos).collect::<Vec<_>>() {
+            self.coverage[i] += 1;
+        }
+    }
+
+    pub fn remove_emitter(&mut self, pos: (usize, usize)) {
+        for i in self.cells_in_range(pos).collect::<Vec<_>>() {
+            self.coverage[i] -= 1;
+        }
+    }
+
+    pub fn inverted(&self, (x, y): (usize, usize)) -> bool {
+        self.coverage[y * self.width + x] > 0
+    }
+
+    /// Copies the cells starting `origin` cells across into a narrower field
+    /// `cut`, carrying on round from the left edge past the right
+    pub fn cut_into(&self, origin: usize, cut: &mut Self) {
+        for y in 0..self.height {
+            for x in 0..cut.width {
+                cut.coverage[y * cut.width + x] =
+                    self.coverage[y * self.width + (origin + x) % self.width];
+            }
+        }
+    }
+}
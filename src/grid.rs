@@ -0,0 +1,53 @@
+use crate::Particle;
+
+/// Storage for the world's particles. Everything goes through `get`, `set`
+/// and `modify` so that the memory layout can change without touching the
+/// update rules.
+#[derive(Clone)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    // Row by row, on the heap since it's far too big to move around on the stack
+    cells: Vec<Particle>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Particle::default(); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    fn index(&self, (x, y): (usize, usize)) -> usize {
+        debug_assert!(x < self.width && y < self.height);
+        y * self.width + x
+    }
+
+    #[inline]
+    pub fn get(&self, pos: (usize, usize)) -> Particle {
+        self.cells[self.index(pos)]
+    }
+
+    #[inline]
+    pub fn set(&mut self, pos: (usize, usize), particle: Particle) {
+        let index = self.index(pos);
+        self.cells[index] = particle;
+    }
+
+    #[inline]
+    pub fn modify<T>(&mut self, pos: (usize, usize), f: impl FnOnce(&mut Particle) -> T) -> T {
+        let index = self.index(pos);
+        f(&mut self.cells[index])
+    }
+}
@@ -0,0 +1,35 @@
+use basic_pixels::{World, AMBIENT_TEMPERATURE};
+
+// How far from room temperature a particle has to be to show at full
+// brightness, below and above
+const COLDEST: i32 = 100;
+const HOTTEST: i32 = 1200;
+
+/// Draws every particle colored by its temperature in place of the world:
+/// blue for cold, through grey at room temperature, to red, yellow and white
+/// for hot
+pub fn draw(world: &World, frame: &mut [u8]) {
+    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+        let pos = (i % world.width(), i / world.width());
+        let particle = world.particle(pos);
+        let color = if particle.empty() {
+            [0x00, 0x00, 0x00]
+        } else {
+            let difference = (particle.temperature - AMBIENT_TEMPERATURE) as i32;
+            if difference < 0 {
+                let cold = (-difference).min(COLDEST) * 0xC0 / COLDEST;
+                [0x30, 0x30 + cold as u8 / 2, 0x30 + cold as u8]
+            } else {
+                // Red first, then yellow, then white as it heats up
+                let hot = difference.min(HOTTEST) * 0x2A0 / HOTTEST;
+                [
+                    (0x30 + hot).min(0xFF) as u8,
+                    (0x30 + hot - 0xCF).clamp(0x30, 0xFF) as u8,
+                    (0x30 + hot - 0x1CF).clamp(0x30, 0xFF) as u8,
+                ]
+            }
+        };
+        pixel[..3].copy_from_slice(&color);
+        pixel[3] = 0xFF;
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+
+use basic_pixels::World;
+
+use crate::{WIN_HEIGHT, WIN_WIDTH};
+
+pub const CHECKPOINT_INTERVAL: u32 = 30;
+const MAX_CHECKPOINTS: usize = 64;
+
+pub const TIMELINE_HEIGHT: u32 = 6;
+
+/// Periodic snapshots of the world that the user can jump back to
+pub struct History {
+    checkpoints: VecDeque<World>,
+    // The checkpoint being viewed, or None when following the live world
+    cursor: Option<usize>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            checkpoints: VecDeque::with_capacity(MAX_CHECKPOINTS),
+            cursor: None,
+        }
+    }
+
+    /// Saves a checkpoint, discarding any that came after the one being viewed
+    pub fn record(&mut self, world: &World) {
+        if let Some(cursor) = self.cursor.take() {
+            self.checkpoints.truncate(cursor + 1);
+        }
+        if self.checkpoints.len() == MAX_CHECKPOINTS {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back(world.clone());
+    }
+
+    /// Moves to the checkpoint under a horizontal position on the timeline
+    pub fn seek(&mut self, x: usize) -> Option<&World> {
+        let slot_width = WIN_WIDTH as usize / MAX_CHECKPOINTS;
+        let index = (x / slot_width).min(self.checkpoints.len().checked_sub(1)?);
+        self.cursor = Some(index);
+        self.checkpoints.get(index)
+    }
+
+    pub fn contains(&self, (_, y): (usize, usize)) -> bool {
+        y >= (WIN_HEIGHT - TIMELINE_HEIGHT) as usize
+    }
+
+    pub fn draw(&self, frame: &mut [u8]) {
+        let slot_width = WIN_WIDTH as usize / MAX_CHECKPOINTS;
+        let live = self.checkpoints.len().saturating_sub(1);
+        for (i, pixel) in frame
+            .chunks_exact_mut(4)
+            .skip((WIN_WIDTH * (WIN_HEIGHT - TIMELINE_HEIGHT)) as usize)
+            .enumerate()
+        {
+            let x = i % WIN_WIDTH as usize;
+            let slot = x / slot_width;
+            let rgba = if !x.is_multiple_of(slot_width) || slot >= self.checkpoints.len() {
+                [0x20, 0x20, 0x20, 0xFF]
+            } else if slot == self.cursor.unwrap_or(live) {
+                [0xFF, 0x40, 0x40, 0xFF]
+            } else {
+                [0xA0, 0xA0, 0xA0, 0xFF]
+            };
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+}
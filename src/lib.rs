@@ -0,0 +1,3614 @@
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+//! The falling sand simulation itself, free of any windowing or rendering
+//! library. The `basic_pixels` binary is one frontend for it.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use chunks::Chunks;
+use components::Components;
+use debugger::TracedRng;
+use elements::{CellCtx, Element};
+use gravity::GravityField;
+use grid::Grid;
+use occupancy::Occupancy;
+use pressure::PressureField;
+use profile::Profile;
+use strips::{SpareStrips, Strip};
+use wind::WindField;
+
+pub use gravity::Pull;
+
+mod chunks;
+mod components;
+pub mod debugger;
+mod elements;
+mod gravity;
+mod grid;
+mod occupancy;
+mod pressure;
+pub mod profile;
+pub mod save;
+pub mod scene;
+mod strips;
+#[cfg(test)]
+mod testing;
+mod wind;
+
+// The size of a world unless it's made with `World::with_size`
+pub const GRID_WIDTH: u32 = 320;
+pub const GRID_HEIGHT: u32 = 240;
+
+// How many particles stacked on top of snow start compacting it,
+// and how many ticks of compaction turn it into water
+const SNOW_CRUSH_DEPTH: usize = 12;
+const SNOW_MELT_TICKS: u8 = 200;
+
+// How many ticks a spark lives, and how long a conductor rests afterwards
+// so that the spark doesn't charge the same wire over again
+const SPARK_LIFE: u8 = 3;
+const CONDUCTOR_COOLDOWN: u8 = 6;
+
+// How many ticks a battery waits between sending out sparks
+const BATTERY_INTERVAL: u8 = 30;
+
+// How fast falling particles can get, in cells per tick, and how fast they
+// have to be going when they land to splash sideways
+const MAX_FALL_SPEED: i8 = 5;
+const SPLASH_SPEED: i8 = 3;
+
+// How much of a hard landing's speed knocks the powder underneath sideways,
+// and up into the air off to either side, as one part in n
+const IMPACT_SIDEWAYS_SHARE: i8 = 2;
+const IMPACT_UPWARDS_SHARE: i8 = 2;
+
+// How many cells a meteor falls per tick, and the size and power of its blast
+const METEOR_SPEED: usize = 4;
+const METEOR_BLAST_RADIUS: i32 = 8;
+const METEOR_BLAST_POWER: i32 = 5;
+
+// An explosion clears out a crater of its radius, then sends a shockwave out
+// SHOCKWAVE_SPEED cells a tick. The shockwave throws the loose particles it
+// passes up and away at its power in cells per tick, and weakens by one each
+// tick until it dies out.
+const SHOCKWAVE_SPEED: i32 = 3;
+const GUNPOWDER_BLAST_RADIUS: i32 = 2;
+const GUNPOWDER_BLAST_POWER: i32 = 2;
+const TNT_BLAST_RADIUS: i32 = 5;
+const TNT_BLAST_POWER: i32 = 4;
+
+// How long a lit fuse cell smoulders before lighting its neighbors,
+// and before it burns away completely
+const FUSE_SPREAD_TICKS: u8 = 8;
+const FUSE_BURN_TICKS: u8 = 16;
+
+// Flammable kinds catch from heat next to them with a chance of their
+// flammability in FLAMMABILITY_SCALE per tick, and give off flames with the
+// same chance while they burn. Burn times are counted 1 in every BURN_RATE ticks.
+const FLAMMABILITY_SCALE: u32 = 100;
+const BURN_RATE: u32 = 4;
+
+// How long a flame burns before it goes up in smoke, and how long the smoke
+// takes to clear
+const FIRE_LIFE: std::ops::Range<u8> = 4..24;
+const SMOKE_LIFE: std::ops::Range<u8> = 60..240;
+
+// The odds (1 in n per tick) of an ant taking a step, turning around on a
+// whim, digging into the sand in front of it, and drowning in water
+const ANT_STEP_ODDS: u32 = 2;
+const ANT_TURN_ODDS: u32 = 60;
+const ANT_DIG_ODDS: u32 = 6;
+const ANT_DROWN_ODDS: u32 = 40;
+
+// How many cells out from its seed a crystal grows, and the odds (1 in n per
+// tick) of it growing into plain water and into saltwater
+const CRYSTAL_SIZE: u8 = 14;
+const CRYSTAL_GROW_ODDS: u32 = 120;
+const CRYSTAL_SALT_GROW_ODDS: u32 = 15;
+
+// How many ticks the flash left behind by antimatter lasts
+const FLASH_TICKS: u8 = 6;
+
+// The odds (1 in n per tick) of uranium flickering to a new brightness, and
+// how many brightness levels it flickers between
+const URANIUM_FLICKER_ODDS: u32 = 4;
+const URANIUM_GLOW_LEVELS: u8 = 4;
+
+// Air pressure is tracked over square blocks this many cells across. Every
+// gas particle adds GAS_PRESSURE to its block each tick and blasts add
+// BLAST_PRESSURE per cell of radius, while one part in PRESSURE_LEAK leaks
+// away. Powders and liquids get blown about once the difference across their
+// block passes PRESSURE_PUSH_THRESHOLD, the more likely the bigger it is.
+const PRESSURE_BLOCK_SIZE: usize = 8;
+const GAS_PRESSURE: i32 = 1;
+const BLAST_PRESSURE: i32 = 1500;
+const PRESSURE_LEAK: i32 = 16;
+const PRESSURE_PUSH_THRESHOLD: i32 = 400;
+const PRESSURE_PUSH_SCALE: u32 = 4000;
+
+// Wind is tracked over square blocks this many cells across, in
+// 1/WIND_SCALE cells per tick. One part in WIND_CALM of it dies down every
+// tick, and the wind tool blows WIND_GUST for every cell the cursor is
+// dragged.
+const WIND_BLOCK_SIZE: usize = 8;
+const WIND_SCALE: i32 = 256;
+const WIND_CALM: i32 = 24;
+pub const WIND_GUST: i32 = 48;
+
+// Temperatures, in degrees. Particles start out at room temperature unless
+// they're painted in as something cold or hot. Steam only condenses on its
+// own once it's colder than the room.
+pub const AMBIENT_TEMPERATURE: i16 = 20;
+const FREEZING_POINT: i16 = 0;
+const BOILING_POINT: i16 = 100;
+const CONDENSING_POINT: i16 = AMBIENT_TEMPERATURE;
+const LAVA_FREEZING_POINT: i16 = 400;
+const SAND_MELTING_POINT: i16 = 500;
+
+// Conductivities are out of this. Neighboring particles pass on the
+// difference in their temperatures times the lower of their conductivities,
+// and empty cells count as air at room temperature.
+const CONDUCTION_SCALE: i32 = 256;
+const AIR_CONDUCTIVITY: i32 = 1;
+
+// How much has to be piled on top of sand before it starts turning into
+// sandstone, and the odds (1 in n per tick) of that happening once it is
+const SAND_COMPACT_DEPTH: usize = 30;
+const SAND_COMPACT_ODDS: u32 = 3000;
+
+// With level of detail enabled, blocks further than the radius from the focus
+// (in blocks) only update once every stride ticks
+const LOD_BLOCK_SIZE: usize = 32;
+const LOD_BLOCK_RADIUS: usize = 2;
+const LOD_STRIDE: u32 = 4;
+
+// With sleeping enabled, chunks where nothing has changed for this many ticks,
+// and whose neighbors have been just as quiet, are skipped until something
+// disturbs them
+const CHUNK_SIZE: usize = 32;
+const CHUNK_SLEEP_TICKS: u32 = 60;
+
+// With parallel updates, each column of chunks is cut out along with this
+// many cells of the world either side of it. Anything in it that reaches
+// further, like a big enough blast, is cut short.
+const STRIP_MARGIN: usize = CHUNK_SIZE / 2;
+
+// How far an anti-gravity block's field reaches, and the tint of the empty
+// cells inside it
+const ANTIGRAVITY_RADIUS: i32 = 12;
+const ANTIGRAVITY_AURA: [u8; 4] = [0x14, 0x08, 0x24, 0xFF];
+
+// How many cells nitro can fall before landing sets it off, how fast (in
+// cells per tick) something has to hit it to set it off, and how big the
+// blast is
+const NITRO_FALL_LIMIT: u8 = 6;
+const NITRO_IMPACT_SPEED: i8 = 3;
+const NITRO_BLAST_RADIUS: i32 = 6;
+const NITRO_BLAST_POWER: i32 = 4;
+
+// How long lit thermite burns for, and the odds (1 in n per tick) of it
+// melting through the metal or stone under it
+const THERMITE_BURN_TICKS: u8 = 150;
+const THERMITE_MELT_ODDS: u32 = 3;
+
+// The pore sizes grates can be painted with. Anything with a grain size no
+// bigger than a grate's pore size falls through it.
+pub const GRATE_PORE_SIZES: std::ops::RangeInclusive<u8> = 0..=3;
+
+// How many cells a lit firework climbs per tick and for how many ticks before
+// it bursts, the size of the burst and how long its embers glow for
+const FIREWORK_SPEED: usize = 3;
+const FIREWORK_FLIGHT_TICKS: u8 = 14;
+const FIREWORK_BURST_RADIUS: i32 = 7;
+const EMBER_LIFE: std::ops::Range<u8> = 20..50;
+
+// Each firework bursts in the color of one of these kinds
+const FIREWORK_COLORS: [Kind; 5] = [
+    Kind::Spark,
+    Kind::Virus,
+    Kind::Clone,
+    Kind::Water,
+    Kind::Fire,
+];
+
+// How long a lit wick cell burns for, counted 1 in every WICK_BURN_RATE
+// ticks, and the size of the candles placed with C
+const WICK_BURN_TICKS: u8 = 60;
+const WICK_BURN_RATE: u32 = 4;
+const CANDLE_HEIGHT: usize = 16;
+
+// Gases drift 1 in this many ticks, rising if they're lighter than air and
+// sinking if they're heavier, and wandering sideways as far as they disperse
+const GAS_DRIFT_ODDS: u32 = 2;
+const AIR_DENSITY: u8 = 2;
+
+// How many cells helium rises per tick, how light a powder has to be for it
+// to lift, and the odds (1 in n per tick) of it lifting one
+const HELIUM_SPEED: usize = 2;
+const HELIUM_LIFT_DENSITY: u8 = 8;
+const HELIUM_LIFT_ODDS: u32 = 3;
+
+// The odds (1 in n per tick) of soap in water blowing a bubble, how many it
+// blows before it's used up, and how long a bubble lasts
+const SOAP_BUBBLE_ODDS: u32 = 12;
+const SOAP_BUBBLES: u8 = 20;
+const BUBBLE_LIFE: std::ops::Range<u8> = 40..120;
+
+// How much water a sponge cell can hold, and the odds (1 in n per tick) of
+// it catching fire next to something hot
+const SPONGE_CAPACITY: u8 = 6;
+const SPONGE_BURN_ODDS: u32 = 20;
+
+// The odds (1 in n per tick) of dry ice turning into gas, when cold and
+// when it has heat next to it
+const DRY_ICE_SUBLIMATE_ODDS: u32 = 600;
+const DRY_ICE_HEATED_SUBLIMATE_ODDS: u32 = 30;
+
+// The odds (1 in n per tick) of wet sand drying out with no liquid around it
+const WET_SAND_DRY_ODDS: u32 = 400;
+
+// How many times more easily a sticky particle loses its grip hanging from
+// more of itself in a drip than stuck to a wall or ceiling
+const DRIP_WEAKNESS: u32 = 20;
+
+// The odds (1 in n per tick) of water with open air above it evaporating,
+// once it's this warm or next to something hot, and of steam condensing into
+// a droplet under a ceiling cooler than boiling
+const WATER_EVAPORATE_ODDS: u32 = 3000;
+const WATER_EVAPORATING_POINT: i16 = 50;
+const STEAM_CONDENSE_ODDS: u32 = 20;
+
+// How wet an absorbent particle gets next to water, how wet a powder has to
+// be to clump instead of slipping, how much wetness adds a point of density,
+// and the odds (1 in n per tick) of it soaking up some of a wetter
+// neighbor's water, and of it drying a step without and with heat next to it
+const MAX_WETNESS: u8 = 8;
+const CLUMP_WETNESS: u8 = 3;
+const WETNESS_PER_DENSITY: u8 = 2;
+const SOAK_ODDS: u32 = 8;
+const DRY_ODDS: u32 = 150;
+const HEATED_DRY_ODDS: u32 = 6;
+
+// How long a seed has to sit on wet ground before it sprouts,
+// and how tall the plant it grows into can get
+const SEED_GERMINATE_TICKS: u8 = 90;
+const PLANT_HEIGHT: std::ops::Range<u8> = 8..20;
+
+// How long cement has to sit still before it sets into stone
+const CEMENT_SET_TICKS: u8 = 180;
+
+// How many ticks between structural integrity checks
+const INTEGRITY_INTERVAL: u32 = 8;
+
+// Every LEVEL_INTERVAL ticks, each connected body of runny liquid moves up to
+// LEVEL_TRANSFERS particles from its highest surface down to its lowest open
+// spots, so that it levels out even through U-bends
+const LEVEL_INTERVAL: u32 = 4;
+const LEVEL_TRANSFERS: usize = 4;
+
+// How long an earthquake lasts, and how much it weakens structures meanwhile
+const QUAKE_TICKS: u32 = 180;
+const QUAKE_STRESS_DIVISOR: usize = 3;
+
+// Every kind, the element in `elements` whose rules it follows, and its
+// color. The `Kind` enum, `Kind::ALL` and the lookup tables behind
+// `element`, `color` and `name` are all generated from this one list, so a
+// new kind only needs a line here plus an element for its rules.
+macro_rules! kinds {
+    ($($kind:ident: $element:ident => $color:expr,)*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Kind {
+            $($kind,)*
+        }
+
+        impl Kind {
+            pub const ALL: [Kind; [$(stringify!($kind)),*].len()] = [$(Kind::$kind),*];
+
+            const ELEMENTS: [&'static dyn Element; Self::ALL.len()] = [$(&elements::$element),*];
+            const COLORS: [[u8; 4]; Self::ALL.len()] = [$($color),*];
+            const NAMES: [&'static str; Self::ALL.len()] = [$(stringify!($kind)),*];
+        }
+    };
+}
+
+kinds! {
+    Empty: Inert => [0, 0, 0, 0],
+    Sand: Sand => [0xC2, 0xB2, 0x80, 0xFF],
+    Gravel: Powder => [0x60, 0x60, 0x60, 0xFF],
+    Water: Water => [0x00, 0x96, 0xFF, 0xFF],
+    Stone: Inert => [0xCC, 0xCC, 0xCC, 0xFF],
+    Salt: Powder => [0xF0, 0xF0, 0xF0, 0xFF],
+    Saltwater: Saltwater => [0x20, 0x80, 0xE0, 0xFF],
+    Fire: Fire => [0xFF, 0x60, 0x10, 0xFF],
+    Snow: Snow => [0xE8, 0xF4, 0xFF, 0xFF],
+    Mud: Mud => [0x5C, 0x40, 0x24, 0xFF],
+    Metal: Inert => [0x8C, 0x9C, 0xA8, 0xFF],
+    Spark: Spark => [0xFF, 0xFF, 0x70, 0xFF],
+    Gunpowder: Powder => [0x30, 0x30, 0x30, 0xFF],
+    Meteor: Meteor => [0xFF, 0xE0, 0xA0, 0xFF],
+    Clone: Clone => [0xB0, 0x40, 0xC0, 0xFF],
+    Void: Void => [0x30, 0x10, 0x40, 0xFF],
+    Fuse: Fuse => [0x80, 0x50, 0x30, 0xFF],
+    Wax: Wax => [0xF0, 0xE0, 0xB0, 0xFF],
+    MoltenWax: MoltenWax => [0xFF, 0xD0, 0x80, 0xFF],
+    Lava: Viscous => [0xFF, 0x40, 0x00, 0xFF],
+    Glass: Inert => [0xB8, 0xDC, 0xE8, 0xFF],
+    Virus: Virus => [0x60, 0xE0, 0x30, 0xFF],
+    Seed: Seed => [0x9A, 0x7B, 0x4F, 0xFF],
+    Plant: Plant => [0x30, 0xA0, 0x30, 0xFF],
+    Mercury: Liquid => [0xB8, 0xB8, 0xC8, 0xFF],
+    Slime: Viscous => [0x7C, 0xD0, 0x40, 0xFF],
+    Cement: Cement => [0x9A, 0x96, 0x8C, 0xFF],
+    Coal: Powder => [0x2A, 0x26, 0x24, 0xFF],
+    Gas: Gas => [0x58, 0x68, 0x48, 0xFF],
+    AntiGravity: Inert => [0x90, 0x50, 0xE0, 0xFF],
+    Nitro: Nitro => [0xE8, 0xE0, 0x60, 0xFF],
+    Wick: Wick => [0xF8, 0xF4, 0xE8, 0xFF],
+    Glue: Glue => [0xF0, 0xEC, 0xD8, 0xFF],
+    Composite: Inert => [0xD0, 0xC8, 0xB0, 0xFF],
+    Thermite: Thermite => [0x8C, 0x4A, 0x3A, 0xFF],
+    DryIce: DryIce => [0xD8, 0xE8, 0xF0, 0xFF],
+    Co2: Drifting => [0x38, 0x3C, 0x44, 0xFF],
+    Grate: Inert => [0x70, 0x78, 0x88, 0xFF],
+    WetSand: WetSand => [0x8C, 0x7C, 0x54, 0xFF],
+    Battery: Battery => [0x30, 0x40, 0x30, 0xFF],
+    WaterSource: WaterSource => [0x10, 0x50, 0xA0, 0xFF],
+    Sandstone: Inert => [0xB4, 0x98, 0x6C, 0xFF],
+    Drain: Drain => [0x20, 0x28, 0x38, 0xFF],
+    Firework: Firework => [0xC0, 0x30, 0x40, 0xFF],
+    Ember: Ember => [0xFF, 0xC0, 0x60, 0xFF],
+    Helium: Helium => [0xF4, 0xE4, 0xF0, 0xFF],
+    Rust: Powder => [0x8E, 0x44, 0x1E, 0xFF],
+    Soap: Soap => [0xF0, 0xC8, 0xE8, 0xFF],
+    Bubble: Bubble => [0xC8, 0xEC, 0xFF, 0xFF],
+    Sponge: Sponge => [0xE8, 0xD0, 0x40, 0xFF],
+    Tar: Viscous => [0x1C, 0x18, 0x14, 0xFF],
+    Smoke: Drifting => [0x50, 0x50, 0x50, 0xFF],
+    Uranium: Uranium => [0x4C, 0x9C, 0x34, 0xFF],
+    Antimatter: Powder => [0xE0, 0x60, 0xFF, 0xFF],
+    Flash: Inert => [0xFF, 0xFF, 0xFF, 0xFF],
+    Crystal: Crystal => [0xA0, 0xE8, 0xF0, 0xFF],
+    Ant: Ant => [0x70, 0x20, 0x14, 0xFF],
+    Ice: Inert => [0xB4, 0xDC, 0xF8, 0xFF],
+    Steam: Steam => [0xD4, 0xD8, 0xE0, 0xFF],
+    Tnt: Inert => [0xC8, 0x28, 0x28, 0xFF],
+    Acid: Liquid => [0x90, 0xF0, 0x30, 0xFF],
+}
+
+impl Kind {
+    /// The rules this kind follows on its turn
+    fn element(&self) -> &'static dyn Element {
+        Self::ELEMENTS[*self as usize]
+    }
+
+    pub fn color(&self) -> [u8; 4] {
+        Self::COLORS[*self as usize]
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    /// Slow-acting kinds only need to update once every this many ticks.
+    /// Their odds of doing anything should be scaled up to match.
+    pub fn update_stride(&self) -> u32 {
+        match *self {
+            Self::Wax | Self::Plant => 4,
+            _ => 1,
+        }
+    }
+
+    /// What happens when this kind touches `other`: the kinds each of them
+    /// turn into, and the odds (1 in n per tick) of it happening
+    pub fn contact_reaction(&self, other: Kind) -> Option<(Kind, Kind, u32)> {
+        match (*self, other) {
+            (Self::Salt, Self::Water) => Some((Self::Empty, Self::Saltwater, 10)),
+            (Self::Sand, Self::Water) => Some((Self::WetSand, Self::Empty, 20)),
+            (Self::WetSand, Self::Water) => Some((Self::Mud, Self::Empty, 100)),
+            (Self::Lava, Self::Water) => Some((Self::Stone, Self::Empty, 4)),
+            (Self::Metal, Self::Water) => Some((Self::Rust, Self::Water, 600)),
+            (Self::Metal, Self::Saltwater) => Some((Self::Rust, Self::Saltwater, 200)),
+            (Self::Co2, Self::Fire) => Some((Self::Co2, Self::Empty, 2)),
+            (Self::Antimatter, other) | (other, Self::Antimatter)
+                if !matches!(other, Self::Empty | Self::Antimatter | Self::Flash) =>
+            {
+                Some((Self::Flash, Self::Flash, 1))
+            }
+            // Corrosives are used up eating away at whatever they can
+            (corrosive, other) | (other, corrosive) if corrosive.corrosive() => other
+                .corrosion_resistance()
+                .map(|odds| (Self::Empty, Self::Empty, odds)),
+            _ => None,
+        }
+    }
+
+    /// How far a solid can reach sideways from its nearest support before it cracks
+    pub fn max_overhang(&self) -> usize {
+        match *self {
+            Self::Stone => 24,
+            Self::Glass => 8,
+            Self::Sandstone => 6,
+            Self::Composite => 16,
+            Self::Sponge => 10,
+            Self::Metal
+            | Self::Spark
+            | Self::Clone
+            | Self::Void
+            | Self::Fuse
+            | Self::AntiGravity
+            | Self::Grate
+            | Self::Battery
+            | Self::WaterSource
+            | Self::Drain
+            | Self::Crystal
+            | Self::Ice
+            | Self::Tnt => usize::MAX,
+            _ => 0,
+        }
+    }
+
+    /// How a powder slips off the side of a pile, if it does: as far as `reach`
+    /// cells over and one down, wherever the drop there is at least `drop`
+    /// cells deep. Short reaches and deep drops stack up into steep piles.
+    pub fn slip(&self) -> Option<(i32, usize)> {
+        match *self {
+            Self::Gravel => Some((1, 2)),
+            Self::WetSand => Some((1, 3)),
+            Self::Snow => Some((2, 1)),
+            kind if kind.is_powder() => Some((1, 1)),
+            _ => None,
+        }
+    }
+
+    /// What a solid crumbles into when it cracks
+    pub fn rubble(&self) -> Kind {
+        match *self {
+            Self::Stone => Self::Gravel,
+            Self::Glass | Self::Sandstone => Self::Sand,
+            _ => Self::Empty,
+        }
+    }
+
+    pub fn is_powder(&self) -> bool {
+        matches!(
+            *self,
+            Self::Sand
+                | Self::Gravel
+                | Self::Salt
+                | Self::Snow
+                | Self::Gunpowder
+                | Self::Seed
+                | Self::Coal
+                | Self::Thermite
+                | Self::DryIce
+                | Self::WetSand
+                | Self::Firework
+                | Self::Rust
+                | Self::Soap
+                | Self::Uranium
+                | Self::Antimatter
+        )
+    }
+
+    /// Relative weight, which decides what floats on what. Only meaningful
+    /// for kinds that fall or float.
+    pub fn density(&self) -> u8 {
+        match *self {
+            Self::Helium => 0,
+            Self::Gas | Self::Smoke | Self::Steam => 1,
+            Self::Co2 => 3,
+            Self::Snow => 3,
+            Self::DryIce => 15,
+            Self::Seed => 8,
+            Self::Ice => 9,
+            Self::MoltenWax => 9,
+            Self::Water => 10,
+            Self::Slime => 12,
+            Self::Saltwater | Self::Acid => 11,
+            Self::Soap => 12,
+            Self::Nitro => 13,
+            Self::Coal | Self::Glue | Self::Tar => 14,
+            Self::Sand | Self::Antimatter => 16,
+            Self::Gunpowder | Self::WetSand | Self::Firework => 17,
+            Self::Gravel | Self::Mud | Self::Rust => 18,
+            Self::Cement => 19,
+            Self::Thermite => 20,
+            Self::Salt => 21,
+            Self::Uranium => 22,
+            Self::Lava => 25,
+            Self::Mercury => 135,
+            _ => u8::MAX,
+        }
+    }
+
+    /// Kinds that bob up through any liquid heavier than them, instead of
+    /// staying wherever they're put
+    pub fn floats(&self) -> bool {
+        matches!(*self, Self::Seed | Self::Snow | Self::Ice)
+    }
+
+    /// Whether a falling particle of this kind would push `other` out of the way
+    pub fn sinks_through(&self, other: Kind) -> bool {
+        other.gives_way_to_sinking(self.density())
+    }
+
+    /// Whether a rising particle of this kind would push `other` out of the way
+    pub fn rises_through(&self, other: Kind) -> bool {
+        other.gives_way_to_rising(self.density())
+    }
+
+    // Whether this kind moves out of the way of something `density` heavy
+    // falling into it
+    fn gives_way_to_sinking(&self, density: u8) -> bool {
+        *self == Self::Empty || ((self.is_liquid() || self.is_gas()) && self.density() < density)
+    }
+
+    // Whether this kind moves out of the way of something `density` heavy
+    // floating up into it
+    fn gives_way_to_rising(&self, density: u8) -> bool {
+        *self == Self::Empty || ((self.is_liquid() || self.is_gas()) && self.density() > density)
+    }
+
+    pub fn is_liquid(&self) -> bool {
+        matches!(
+            *self,
+            Self::Water
+                | Self::Saltwater
+                | Self::MoltenWax
+                | Self::Lava
+                | Self::Mercury
+                | Self::Slime
+                | Self::Cement
+                | Self::Nitro
+                | Self::Glue
+                | Self::Tar
+                | Self::Acid
+        )
+    }
+
+    /// How reluctantly a liquid spreads sideways: it only gets the chance to
+    /// 1 in this many ticks
+    pub fn viscosity(&self) -> u32 {
+        match *self {
+            Self::Cement => 4,
+            Self::Glue => 6,
+            Self::Mud | Self::MoltenWax | Self::Lava => 8,
+            Self::Slime => 30,
+            Self::Tar => 40,
+            _ => 1,
+        }
+    }
+
+    /// How many cells a liquid can skim across the top of a pool at once
+    pub fn spread(&self) -> i32 {
+        match *self {
+            Self::Water | Self::Saltwater | Self::Nitro | Self::Acid => 4,
+            Self::Mercury => 3,
+            _ => 1,
+        }
+    }
+
+    /// How coarse a falling kind is, for deciding what gets through a grate
+    pub fn grain_size(&self) -> u8 {
+        match *self {
+            Self::Sand | Self::Salt | Self::Gunpowder | Self::Thermite => 1,
+            Self::Gravel => 3,
+            kind if kind.is_liquid() => 0,
+            _ => 2,
+        }
+    }
+
+    /// The temperature heat sources and sinks are held at, whatever is around them
+    pub fn fixed_temperature(&self) -> Option<i16> {
+        match *self {
+            Self::Meteor => Some(1500),
+            Self::Fire => Some(600),
+            Self::Ember => Some(400),
+            Self::Uranium => Some(300),
+            Self::DryIce => Some(-78),
+            _ => None,
+        }
+    }
+
+    /// The temperature a particle of this kind is painted in at
+    pub fn painted_temperature(&self) -> i16 {
+        match *self {
+            Self::Ice => -20,
+            Self::Steam => BOILING_POINT + 20,
+            Self::Lava => 1100,
+            kind => kind.fixed_temperature().unwrap_or(AMBIENT_TEMPERATURE),
+        }
+    }
+
+    /// The temperature at and above which this kind melts, and what into
+    pub fn melting_point(&self) -> Option<(i16, Kind)> {
+        match *self {
+            Self::Ice => Some((FREEZING_POINT, Self::Water)),
+            Self::Sand => Some((SAND_MELTING_POINT, Self::Glass)),
+            _ => None,
+        }
+    }
+
+    /// The temperature at and above which this kind boils, and what into
+    pub fn boiling_point(&self) -> Option<(i16, Kind)> {
+        match *self {
+            Self::Water => Some((BOILING_POINT, Self::Steam)),
+            _ => None,
+        }
+    }
+
+    /// The temperature below which this kind freezes, or for a gas condenses,
+    /// and what into
+    pub fn freezing_point(&self) -> Option<(i16, Kind)> {
+        match *self {
+            Self::Water => Some((FREEZING_POINT, Self::Ice)),
+            Self::Steam => Some((CONDENSING_POINT, Self::Water)),
+            Self::Lava => Some((LAVA_FREEZING_POINT, Self::Stone)),
+            _ => None,
+        }
+    }
+
+    /// What this kind turns into at `temperature`, if that's past one of its
+    /// melting, boiling or freezing points
+    pub fn state_change(&self, temperature: i16) -> Option<Kind> {
+        let reached = |point: Option<(i16, Kind)>| point.filter(|&(at, _)| temperature >= at);
+        reached(self.boiling_point())
+            .or(reached(self.melting_point()))
+            .or(self.freezing_point().filter(|&(at, _)| temperature < at))
+            .map(|(_, into)| into)
+    }
+
+    /// How readily heat passes through this kind, out of CONDUCTION_SCALE
+    pub fn conductivity(&self) -> i32 {
+        match *self {
+            Self::Metal | Self::Mercury => 56,
+            Self::Water | Self::Saltwater | Self::Ice => 28,
+            Self::Sponge | Self::Wax | Self::Snow => 6,
+            kind if kind.is_gas() => 4,
+            _ => 16,
+        }
+    }
+
+    /// How much harder the wind has to blow to carry this kind along than it
+    /// does for smoke, if it can at all
+    pub fn wind_resistance(&self) -> Option<i32> {
+        match *self {
+            Self::Fire | Self::Ember => Some(1),
+            kind if kind.is_gas() => Some(1),
+            Self::Snow | Self::Seed => Some(3),
+            Self::Sand | Self::Salt => Some(8),
+            _ => None,
+        }
+    }
+
+    /// How many cells sideways a gas can wander in one drift
+    pub fn dispersion(&self) -> i32 {
+        match *self {
+            Self::Gas | Self::Steam => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn is_gas(&self) -> bool {
+        matches!(
+            *self,
+            Self::Gas | Self::Co2 | Self::Helium | Self::Smoke | Self::Steam
+        )
+    }
+
+    /// Kinds that heat up and evaporate their surroundings
+    pub fn is_hot(&self) -> bool {
+        matches!(
+            *self,
+            Self::Fire | Self::Meteor | Self::Lava | Self::Ember | Self::Uranium
+        )
+    }
+
+    /// Static kinds that join together into structures
+    pub fn is_solid(&self) -> bool {
+        matches!(
+            *self,
+            Self::Stone
+                | Self::Metal
+                | Self::Spark
+                | Self::Clone
+                | Self::Void
+                | Self::Fuse
+                | Self::Glass
+                | Self::AntiGravity
+                | Self::Composite
+                | Self::Grate
+                | Self::Battery
+                | Self::WaterSource
+                | Self::Sandstone
+                | Self::Drain
+                | Self::Sponge
+                | Self::Crystal
+                | Self::Ice
+                | Self::Tnt
+        )
+    }
+
+    /// Kinds that explosions can't destroy
+    pub fn blast_resistant(&self) -> bool {
+        matches!(*self, Self::Metal | Self::Spark)
+    }
+
+    /// Kinds that sparks can travel through
+    pub fn conducts(&self) -> bool {
+        matches!(*self, Self::Metal | Self::Saltwater | Self::Mercury)
+    }
+
+    /// Kinds that eat away at others, at rates set by their corrosion resistance
+    pub fn corrosive(&self) -> bool {
+        matches!(*self, Self::Acid)
+    }
+
+    /// The odds (1 in n per tick) of a corrosive eating away this kind where
+    /// they touch, if it can at all
+    pub fn corrosion_resistance(&self) -> Option<u32> {
+        match *self {
+            Self::Plant | Self::Seed | Self::Virus | Self::Ant | Self::Slime => Some(4),
+            Self::Sponge | Self::Fuse | Self::Wick | Self::Snow | Self::Bubble => Some(8),
+            Self::Salt | Self::Rust | Self::Wax | Self::Gunpowder | Self::Coal => Some(15),
+            Self::Sand | Self::WetSand | Self::Mud | Self::Ice | Self::Tnt => Some(25),
+            Self::Gravel | Self::Sandstone | Self::Cement | Self::Composite => Some(40),
+            Self::Stone | Self::Metal | Self::Battery => Some(80),
+            _ => None,
+        }
+    }
+
+    /// How readily this kind catches fire from heat and sparks, and spreads
+    /// it once lit, out of FLAMMABILITY_SCALE. Zero if it doesn't burn.
+    pub fn flammability(&self) -> u32 {
+        match *self {
+            Self::Plant | Self::Virus => 50,
+            Self::Coal => 16,
+            Self::Tar => 10,
+            _ => 0,
+        }
+    }
+
+    /// How well this kind sticks to walls and ceilings instead of falling, as
+    /// the odds (1 in n per tick) of it losing its grip. Zero if it doesn't
+    /// stick at all.
+    pub fn adhesion(&self) -> u32 {
+        match *self {
+            Self::Slime => 2000,
+            Self::Tar => 1000,
+            Self::WetSand => 400,
+            Self::Mud => 200,
+            _ => 0,
+        }
+    }
+
+    /// Kinds that soak up water from around them and pass it on to each other
+    pub fn absorbent(&self) -> bool {
+        matches!(
+            *self,
+            Self::Sand
+                | Self::Gravel
+                | Self::Gunpowder
+                | Self::Coal
+                | Self::Thermite
+                | Self::Rust
+                | Self::Plant
+        )
+    }
+
+    /// How long this kind burns for once lit, or zero if it goes up all at once
+    pub fn burn_time(&self) -> u8 {
+        match *self {
+            Self::Coal => 250,
+            Self::Tar => 150,
+            _ => 0,
+        }
+    }
+
+    /// What this kind leaves behind once it has burnt out
+    pub fn burn_product(&self) -> Kind {
+        match *self {
+            Self::Tar => Self::Smoke,
+            _ => Self::Fire,
+        }
+    }
+
+    /// How many ticks a short-lived kind lasts, anywhere in the range, and what
+    /// it decays into at the end
+    pub fn lifetime(&self) -> Option<(std::ops::Range<u8>, Kind)> {
+        match *self {
+            Self::Fire => Some((FIRE_LIFE, Self::Smoke)),
+            Self::Smoke => Some((SMOKE_LIFE, Self::Empty)),
+            Self::Ember => Some((EMBER_LIFE, Self::Empty)),
+            Self::Bubble => Some((BUBBLE_LIFE, Self::Water)),
+            Self::Flash => Some((FLASH_TICKS..FLASH_TICKS + 1, Self::Empty)),
+            _ => None,
+        }
+    }
+
+    /// The radius and power of the blast this kind goes off with when heat or
+    /// a spark reaches it, if it's explosive
+    pub fn blast(&self) -> Option<(i32, i32)> {
+        match *self {
+            Self::Gunpowder => Some((GUNPOWDER_BLAST_RADIUS, GUNPOWDER_BLAST_POWER)),
+            Self::Tnt => Some((TNT_BLAST_RADIUS, TNT_BLAST_POWER)),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for Kind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown kind `{}`", name))
+    }
+}
+
+// The bits of a particle's flags byte. The top four hold its wetness.
+const TOUCHED: u8 = 1 << 0; // Which turn it last had, so it only gets one per tick
+const BURNING: u8 = 1 << 1;
+const WETNESS_SHIFT: u32 = 4;
+
+// Eight bytes to a particle, so that the update scan pulls as few through the
+// cache as it can
+const _: () = assert!(std::mem::size_of::<Particle>() == 8);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Particle {
+    kind: u8,
+    flags: u8,
+    // Kind-specific progress, e.g. how compacted a snow particle is or how
+    // long a short-lived one has lasted so far
+    counter: u8,
+    stored: u8, // Kind-specific memory, e.g. the conductor a spark is passing through
+    pub temperature: i16,
+    pub velocity: (i8, i8), // In cells per tick, rightwards and downwards
+}
+
+impl Particle {
+    /// A particle of a kind, at room temperature and standing still
+    pub fn new(kind: Kind) -> Self {
+        Particle {
+            kind: kind as u8,
+            ..Particle::default()
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        Kind::ALL[self.kind as usize]
+    }
+
+    pub fn set_kind(&mut self, kind: Kind) {
+        self.kind = kind as u8;
+    }
+
+    /// The same particle turned into another kind
+    pub fn with_kind(mut self, kind: Kind) -> Self {
+        self.set_kind(kind);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: i16) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: (i8, i8)) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    fn with_counter(mut self, counter: u8) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    fn stored(&self) -> Kind {
+        Kind::ALL[self.stored as usize]
+    }
+
+    fn set_stored(&mut self, stored: Kind) {
+        self.stored = stored as u8;
+    }
+
+    fn with_stored(mut self, stored: Kind) -> Self {
+        self.set_stored(stored);
+        self
+    }
+
+    // The turn it last had
+    fn turn(&self) -> Turn {
+        Turn(self.flags & TOUCHED != 0)
+    }
+
+    fn set_turn(&mut self, turn: Turn) {
+        self.flags = (self.flags & !TOUCHED) | if turn.0 { TOUCHED } else { 0 };
+    }
+
+    // The same particle, as if it had already had `turn`
+    fn with_turn(mut self, turn: Turn) -> Self {
+        self.set_turn(turn);
+        self
+    }
+
+    /// Whether it has been lit and is burning down
+    pub fn burning(&self) -> bool {
+        self.flags & BURNING != 0
+    }
+
+    fn set_burning(&mut self, burning: bool) {
+        self.flags = (self.flags & !BURNING) | if burning { BURNING } else { 0 };
+    }
+
+    /// How much water an absorbent particle has soaked up
+    pub fn wetness(&self) -> u8 {
+        self.flags >> WETNESS_SHIFT
+    }
+
+    fn set_wetness(&mut self, wetness: u8) {
+        debug_assert!(wetness <= MAX_WETNESS);
+        self.flags = (self.flags & !(u8::MAX << WETNESS_SHIFT)) | wetness << WETNESS_SHIFT;
+    }
+
+    pub fn wet(&self) -> bool {
+        self.wetness() > 0
+    }
+
+    pub fn empty(&self) -> bool {
+        self.kind() == Kind::Empty
+    }
+
+    /// How heavy the particle is, counting any water it has soaked up
+    pub fn density(&self) -> u8 {
+        self.kind()
+            .density()
+            .saturating_add(self.wetness() / WETNESS_PER_DENSITY)
+    }
+
+    /// Whether this particle would push `other` out of the way as it falls,
+    /// which a wet one does to more than its kind would
+    pub fn sinks_through(&self, other: Kind) -> bool {
+        other.gives_way_to_sinking(self.density())
+    }
+
+    /// Whether this particle would push `other` out of the way as it floats up
+    pub fn rises_through(&self, other: Kind) -> bool {
+        other.gives_way_to_rising(self.density())
+    }
+
+    pub fn color(&self) -> [u8; 4] {
+        match self.kind() {
+            Kind::Fuse if self.burning() => [0xFF, 0x90, 0x30, 0xFF],
+            Kind::Coal if self.burning() => [0xC0, 0x30, 0x10, 0xFF],
+            Kind::Tar if self.burning() => [0x90, 0x28, 0x10, 0xFF],
+            Kind::Wick if self.burning() => [0x40, 0x30, 0x28, 0xFF],
+            Kind::Thermite if self.burning() => [0xFF, 0xF0, 0xC0, 0xFF],
+            Kind::Firework if self.counter > 0 => [0xFF, 0xF0, 0xE0, 0xFF],
+            // Embers glow in the color of the kind their firework picked
+            Kind::Ember => self.stored().color(),
+            // Coarser grates are drawn darker
+            Kind::Grate => {
+                let [r, g, b, a] = self.kind().color();
+                let shade = self.counter * 0x10;
+                [r - shade, g - shade, b - shade, a]
+            }
+            // Flashes fade out as they age
+            Kind::Flash => {
+                let fade = self.counter * (0xC0 / FLASH_TICKS);
+                [0xFF - fade, 0xFF - fade, 0xFF - fade / 2, 0xFF]
+            }
+            // Uranium glows, flickering a little from tick to tick
+            Kind::Uranium => {
+                let [r, g, b, a] = self.kind().color();
+                let glow = self.counter * 0x10;
+                [r + glow / 2, g + glow, b + glow / 2, a]
+            }
+            // Sponges darken as they soak up water
+            Kind::Sponge => {
+                let [r, g, b, a] = self.kind().color();
+                let soaked = self.counter as u16 * 0x60 / SPONGE_CAPACITY as u16;
+                [r - soaked as u8, g - soaked as u8 / 2, b, a]
+            }
+            // Glued powder is a blend of the powder and the glue holding it together
+            Kind::Composite => {
+                let (powder, glue) = (self.stored().color(), Kind::Glue.color());
+                [
+                    ((powder[0] as u16 * 3 + glue[0] as u16) / 4) as u8,
+                    ((powder[1] as u16 * 3 + glue[1] as u16) / 4) as u8,
+                    ((powder[2] as u16 * 3 + glue[2] as u16) / 4) as u8,
+                    0xFF,
+                ]
+            }
+            // Anything that has soaked up water darkens, by up to a third
+            kind if self.wet() => {
+                let darken =
+                    |c: u8| c - (c as u16 * self.wetness() as u16 / (MAX_WETNESS as u16 * 3)) as u8;
+                let [r, g, b, a] = kind.color();
+                [darken(r), darken(g), darken(b), a]
+            }
+            kind => kind.color(),
+        }
+    }
+}
+
+// Whether a tick is odd or even, which is all a particle needs to remember to
+// tell if it has had its turn yet. Particles in a sleeping chunk miss their
+// stamps, so they're all stamped afresh when it wakes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Turn(bool);
+
+impl Default for Particle {
+    fn default() -> Particle {
+        Particle {
+            kind: Kind::Empty as u8,
+            flags: 0,
+            counter: 0,
+            stored: Kind::Empty as u8,
+            temperature: AMBIENT_TEMPERATURE,
+            velocity: (0, 0),
+        }
+    }
+}
+
+impl std::fmt::Debug for Particle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Particle")
+            .field("kind", &self.kind())
+            .field("counter", &self.counter)
+            .field("stored", &self.stored())
+            .field("turn", &self.turn())
+            .field("burning", &self.burning())
+            .field("wetness", &self.wetness())
+            .field("temperature", &self.temperature)
+            .field("velocity", &self.velocity)
+            .finish()
+    }
+}
+
+/// The expanding edge of an explosion, `reach` cells out from its center
+#[derive(Clone, Copy)]
+struct Shockwave {
+    center: (i32, i32),
+    reach: i32,
+    power: i32,
+}
+
+impl Shockwave {
+    /// How fast it throws a particle at `pos`: away from the center, and
+    /// always upwards at no less than half its power
+    fn velocity_at(&self, (x, y): (i32, i32)) -> (i8, i8) {
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        let distance = ((dx * dx + dy * dy) as u32).isqrt().max(1) as i32;
+        (
+            (dx * self.power / distance) as i8,
+            (dy * self.power / distance).min(-(self.power + 1) / 2) as i8,
+        )
+    }
+}
+
+/// A cell whose particle changed during an update, for redrawing only what moved
+#[derive(Clone, Copy)]
+pub struct Change {
+    pub pos: (usize, usize),
+    pub old: Particle,
+    pub new: Particle,
+}
+
+/// One of the four edges of the grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Edge {
+    /// The edge just past a cell's neighbor in the given direction, if that
+    /// neighbor would be off the grid
+    fn beyond(
+        (width, height): (usize, usize),
+        (x, y): (usize, usize),
+        (x_off, y_off): (i32, i32),
+    ) -> Option<Edge> {
+        if x_off < 0 && x == 0 {
+            Some(Edge::Left)
+        } else if x_off > 0 && x == width - 1 {
+            Some(Edge::Right)
+        } else if y_off < 0 && y == 0 {
+            Some(Edge::Top)
+        } else if y_off > 0 && y == height - 1 {
+            Some(Edge::Bottom)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for Edge {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "top" => Ok(Edge::Top),
+            "bottom" => Ok(Edge::Bottom),
+            "left" => Ok(Edge::Left),
+            "right" => Ok(Edge::Right),
+            _ => Err(format!("unknown edge `{}`", name)),
+        }
+    }
+}
+
+/// What lies past the edges of the grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    // Solid walls that hold everything in
+    Wall,
+    // Nothing at all, so anything loose that moves off the edge is lost
+    Void,
+    // The left and right edges join up, so whatever leaves one side comes
+    // back in at the other. The floor and ceiling are still walls.
+    Wrap,
+}
+
+impl Boundary {
+    // The cell `(dx, dy)` away from `pos` in a grid of `size`. Past the edges
+    // there's nothing, unless the sides wrap around.
+    pub(crate) fn offset(
+        self,
+        (width, height): (usize, usize),
+        (x, y): (usize, usize),
+        (dx, dy): (i32, i32),
+    ) -> Option<(usize, usize)> {
+        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+        if ny < 0 || ny >= height as i32 {
+            return None;
+        }
+        if nx < 0 || nx >= width as i32 {
+            if self != Boundary::Wrap {
+                return None;
+            }
+            return Some((nx.rem_euclid(width as i32) as usize, ny as usize));
+        }
+        Some((nx as usize, ny as usize))
+    }
+}
+
+impl std::str::FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "wall" => Ok(Boundary::Wall),
+            "void" => Ok(Boundary::Void),
+            "wrap" => Ok(Boundary::Wrap),
+            _ => Err(format!("unknown boundary `{}`", name)),
+        }
+    }
+}
+
+/// The grid of particles and everything that acts on them
+#[derive(Clone)]
+pub struct World {
+    particles: Grid,
+    changes: Vec<Change>,
+    components: Components,
+    occupancy: Occupancy,
+    gravity: GravityField,
+    /// Which way things fall. The kinds' own rules are written for straight
+    /// down, so anything else falls by the generic `fall_along` instead.
+    pub pull: Pull,
+    pressure: PressureField,
+    wind: WindField,
+    shockwaves: Vec<Shockwave>,
+    // Set when the gravity field changes, since that changes how empty cells look
+    field_changed: bool,
+    /// Whether overhangs crack and unsupported structures fall
+    pub structural_integrity: bool,
+    /// Edges held at a fixed temperature, heating or cooling whatever touches
+    /// them, indexed by `Edge`. The rest let no heat through.
+    pub edge_temperatures: [Option<i16>; 4],
+    pub boundary: Boundary,
+    /// The pore size given to newly painted grates
+    pub grate_pore_size: u8,
+    /// Whether cells far from `lod_focus` update less often
+    pub lod: bool,
+    pub lod_focus: (usize, usize),
+    /// Visits each row's cells in a random order rather than sweeping across
+    pub shuffled_order: bool,
+    /// Whether settled chunks are skipped until something disturbs them
+    pub sleeping: bool,
+    /// Whether particles update on several threads at once, a column of
+    /// chunks to each. Nothing reaches further than half a chunk into the
+    /// next column while they do, and the sides of a wrapped world are walls
+    /// unless it's a whole number of chunks wide.
+    pub parallel: bool,
+    chunks: Chunks,
+    // Where this world sits in a wider one, if it's a strip cut out of it to
+    // update on a thread of its own
+    strip: Option<Strip>,
+    spare_strips: SpareStrips,
+    profile: Profile,
+    // The pull, boundary and edge temperatures the chunks settled under, and
+    // whether they could sleep at all. Changing any of them wakes them all.
+    settled_under: (Pull, Boundary, [Option<i16>; 4], bool),
+    quake_ticks: u32,
+    tick: u32,
+    // Everything random in the simulation comes from here, so that the same
+    // seed and the same edits always play out the same way
+    seed: u64,
+    rng: ChaCha12Rng,
+    /// A cell whose next update gets written up in `trace`, for debugging rules
+    pub watch: Option<(usize, usize)>,
+    pub trace: Vec<String>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    /// An empty world with a random seed
+    pub fn new() -> Self {
+        Self::with_seed(rand::thread_rng().gen::<u32>() as u64)
+    }
+
+    /// An empty world whose randomness all comes from `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_size((GRID_WIDTH as usize, GRID_HEIGHT as usize), seed)
+    }
+
+    /// An empty world of `width` by `height` cells, whose randomness all
+    /// comes from `seed`
+    pub fn with_size((width, height): (usize, usize), seed: u64) -> Self {
+        Self {
+            particles: Grid::new(width, height),
+            changes: Vec::new(),
+            components: Components::new((width, height), Boundary::Wall),
+            occupancy: Occupancy::new(width, height),
+            gravity: GravityField::new(width, height),
+            pull: Pull::DOWN,
+            pressure: PressureField::new(width, height),
+            wind: WindField::new(width, height),
+            shockwaves: Vec::new(),
+            field_changed: false,
+            structural_integrity: true,
+            edge_temperatures: [None; 4],
+            boundary: Boundary::Wall,
+            grate_pore_size: 1,
+            lod: false,
+            lod_focus: (width / 2, height / 2),
+            shuffled_order: false,
+            sleeping: false,
+            parallel: false,
+            chunks: Chunks::new(width, height),
+            strip: None,
+            spare_strips: SpareStrips::default(),
+            profile: Profile::default(),
+            settled_under: (Pull::DOWN, Boundary::Wall, [None; 4], false),
+            quake_ticks: 0,
+            tick: 0,
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            watch: None,
+            trace: Vec::new(),
+        }
+    }
+
+    /// How many cells wide the world is
+    pub fn width(&self) -> usize {
+        self.particles.width()
+    }
+
+    /// How many cells tall the world is
+    pub fn height(&self) -> usize {
+        self.particles.height()
+    }
+
+    /// The particle in a cell
+    pub fn particle(&self, pos: (usize, usize)) -> Particle {
+        self.particles.get(pos)
+    }
+
+    /// How long each phase of the last update took, and the drawing since
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// How many ticks the world has been running
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    // Where a cell is in the whole world, which is further across than it is
+    // in a strip cut out of it, or round past the seam of a wrapped one
+    fn absolute(&self, (x, y): (usize, usize)) -> (usize, usize) {
+        match &self.strip {
+            Some(strip) => ((x + strip.origin) % strip.world_width, y),
+            None => (x, y),
+        }
+    }
+
+    // What particles are stamped with once they've had their turn this tick
+    fn turn(&self) -> Turn {
+        Turn(self.tick % 2 == 1)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Every cell that changed since the world was last drawn
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn clear_changes(&mut self) {
+        self.changes.clear();
+    }
+
+    /// Whether a cell is part of a structure of connected solids
+    pub fn in_structure(&self, pos: (usize, usize)) -> bool {
+        self.components.component_at(pos).is_some()
+    }
+
+    /// Stirs up the wind around a cell, adding `(vx, vy)` to it
+    pub fn gust(&mut self, pos: (usize, usize), velocity: (i32, i32)) {
+        self.wind.gust(pos, velocity);
+        self.chunks.touch(pos);
+    }
+
+    /// Advances the simulation by one tick
+    pub fn update(&mut self) {
+        self.profile.clear();
+        self.tick = self.tick.wrapping_add(1);
+        // Moved out while the rules borrow the world, and put back at the end
+        let mut rng = TracedRng::new(std::mem::replace(
+            &mut self.rng,
+            ChaCha12Rng::seed_from_u64(0),
+        ));
+
+        if self.quake_ticks > 0 {
+            self.quake_ticks -= 1;
+            self.shake(&mut rng);
+        }
+
+        let timing = profile::start();
+        if self.components.boundary() != self.boundary {
+            self.rebuild_components();
+        }
+        if self.structural_integrity && self.tick.is_multiple_of(INTEGRITY_INTERVAL) {
+            self.check_integrity(&mut rng);
+        }
+        if self.structural_integrity {
+            self.drop_loose_structures();
+        }
+        self.profile.record("structures", timing);
+
+        let timing = profile::start();
+        if self.tick.is_multiple_of(LEVEL_INTERVAL) {
+            self.level_liquids(&mut rng);
+        }
+        self.profile.record("leveling", timing);
+        let timing = profile::start();
+        self.conduct_charge();
+        self.profile.record("charge", timing);
+        let timing = profile::start();
+        self.conduct_heat(&mut rng);
+        self.profile.record("heat", timing);
+        let timing = profile::start();
+        self.update_pressure();
+        self.wind.step(WIND_CALM);
+        self.propagate_shockwaves();
+        self.profile.record("air", timing);
+
+        let settings = (
+            self.pull,
+            self.boundary,
+            self.edge_temperatures,
+            self.sleeping,
+        );
+        if settings != self.settled_under {
+            self.settled_under = settings;
+            self.chunks.wake_all();
+        }
+        self.stir_chunks();
+        self.chunks.step();
+        self.restamp_woken_chunks();
+
+        let timing = profile::start();
+        if self.parallel {
+            self.update_strips(&mut rng);
+        } else {
+            rng = self.update_particles(rng);
+        }
+        self.profile.record("particles", timing);
+
+        if let Some(draws) = rng.trace(false) {
+            self.trace.push(format!("random draws: {:?}", draws));
+        }
+        if let Some(pos) = self.watch {
+            if !self.trace.is_empty() {
+                self.trace
+                    .push(format!("afterwards\n{}", debugger::neighborhood(self, pos)));
+            }
+        }
+        self.rng = rng.into_inner();
+    }
+
+    // Gives every particle that's due for it a turn, row by row from the
+    // bottom up. Hands back the generator the rules drew from.
+    fn update_particles(&mut self, mut rng: TracedRng) -> TracedRng {
+        let columns = self
+            .strip
+            .as_ref()
+            .map_or(0..self.width(), |strip| strip.columns.clone());
+        let mut order = Vec::with_capacity(columns.len());
+        for y in (0..self.height()).rev() {
+            // Each row goes one way or the other at random. Whichever particle
+            // moves first gets the pick of the cells, so a fixed order would
+            // pull piles towards one side.
+            let rightward = rng.gen::<bool>();
+            order.clear();
+            for word_i in 0..self.occupancy.words_per_row() {
+                let word = if rightward {
+                    word_i
+                } else {
+                    self.occupancy.words_per_row() - 1 - word_i
+                };
+                if self.occupancy.word(y, word) == 0 {
+                    continue;
+                }
+                let span = self.occupancy.span(word);
+                let span = span.start.max(columns.start)..span.end.min(columns.end);
+                if rightward {
+                    for run in self.awake_runs(y, span) {
+                        order.extend(run);
+                    }
+                } else {
+                    for run in self.awake_runs(y, span).rev() {
+                        order.extend(run.rev());
+                    }
+                }
+            }
+            // Or the row's cells go in no order at all, so that nothing
+            // spreads out any differently one way than the other
+            if self.shuffled_order {
+                order.shuffle(&mut rng);
+            }
+
+            for &x in &order {
+                let turn = self.turn();
+                if self.particles.get((x, y)).turn() == turn {
+                    continue;
+                }
+                self.particles.modify((x, y), |p| p.set_turn(turn));
+
+                let watched = self.watch == Some((x, y));
+                if let Some(draws) = rng.trace(watched) {
+                    self.trace.push(format!("random draws: {:?}", draws));
+                }
+                if watched {
+                    self.trace.push(format!(
+                        "tick {}: {:?} at {:?}, next to\n{}",
+                        self.tick,
+                        self.particles.get((x, y)),
+                        self.absolute((x, y)),
+                        debugger::neighborhood(self, (x, y))
+                    ));
+                }
+
+                if !self.due_for_update((x, y)) {
+                    if watched {
+                        self.trace
+                            .push("skipped, not due for an update".to_string());
+                    }
+                    continue;
+                }
+
+                // Conductors rest for a little while after carrying a spark
+                self.modify((x, y), |particle| {
+                    if particle.kind().conducts() {
+                        particle.counter = particle.counter.saturating_sub(1);
+                    }
+                });
+
+                // Short-lived kinds age, and decay into something else at the
+                // end of their lives
+                let particle = self.particles.get((x, y));
+                if let Some((life, decays_into)) = particle.kind().lifetime() {
+                    if particle.counter >= life.start
+                        && rng.gen_ratio(1, life.end.saturating_sub(particle.counter).max(1) as u32)
+                    {
+                        if watched {
+                            self.trace.push(format!("decayed into {:?}", decays_into));
+                        }
+                        self.replace((x, y), Particle::new(decays_into).with_turn(self.turn()));
+                        continue;
+                    }
+                    self.modify((x, y), |p| p.counter = p.counter.saturating_add(1));
+                }
+
+                // Explosives go off as soon as heat or a spark reaches them
+                if let Some((radius, power)) = self.particles.get((x, y)).kind().blast() {
+                    if self
+                        .find_neighbor((x, y), |p| p.kind().is_hot() || p.kind() == Kind::Spark)
+                        .is_some()
+                    {
+                        if watched {
+                            self.trace.push("set off".to_string());
+                        }
+                        self.explode((x as i32, y as i32), radius, power, &mut rng);
+                        continue;
+                    }
+                }
+
+                // Absorbent kinds get wet and dry out
+                if self.particles.get((x, y)).kind().absorbent() {
+                    self.soak((x, y), &mut rng);
+                }
+
+                // Flammable kinds catch from heat around them and burn down
+                if self.particles.get((x, y)).kind().flammability() > 0
+                    && self.burn((x, y), &mut rng)
+                {
+                    if watched {
+                        self.trace.push("burned up".to_string());
+                    }
+                    continue;
+                }
+
+                if self.react((x, y), &mut rng) {
+                    if watched {
+                        self.trace.push("reacted with a neighbor".to_string());
+                    }
+                    continue;
+                }
+
+                if self.clings((x, y), &mut rng) {
+                    if watched {
+                        self.trace.push("clinging on".to_string());
+                    }
+                    continue;
+                }
+
+                let kind = self.particles.get((x, y)).kind();
+                if (kind.is_powder() || kind.is_liquid())
+                    && self.particles.get((x, y)).velocity.1 < 0
+                    && self.fly((x, y))
+                {
+                    if watched {
+                        self.trace.push("thrown by a blast".to_string());
+                    }
+                    continue;
+                }
+                if self.carry_by_wind((x, y), &mut rng) {
+                    if watched {
+                        self.trace.push("carried along by the wind".to_string());
+                    }
+                    continue;
+                }
+                if (kind.is_powder() || kind.is_liquid()) && self.blow((x, y), &mut rng) {
+                    if watched {
+                        self.trace.push("blown along by air pressure".to_string());
+                    }
+                    continue;
+                }
+
+                // Anything that would fall goes the other way inside an anti-gravity
+                // field, and wherever gravity points when that isn't straight down
+                if kind.is_powder() || kind.is_liquid() {
+                    let (dx, dy) = self.pull.down_at(self.absolute((x, y)), &mut rng);
+                    let down = if self.gravity.inverted((x, y)) {
+                        (-dx, -dy)
+                    } else {
+                        (dx, dy)
+                    };
+                    if down != (0, 1) {
+                        if watched {
+                            self.trace.push(format!("falling towards {:?}", down));
+                        }
+                        self.fall_along((x, y), down, &mut rng);
+                        continue;
+                    }
+                }
+
+                // Fine enough grains drop straight through grates
+                if (kind.is_powder() || kind.is_liquid())
+                    && self.offset((x, y), (0, 2)).is_some()
+                    && self.particles.get((x, y + 1)).kind() == Kind::Grate
+                    && kind.grain_size() <= self.particles.get((x, y + 1)).counter
+                    && kind.sinks_through(self.particles.get((x, y + 2)).kind())
+                {
+                    if watched {
+                        self.trace.push("dropping through a grate".to_string());
+                    }
+                    self.swap((x, y), (x, y + 2));
+                    continue;
+                }
+
+                // Light kinds bob up through heavier liquids
+                if kind.floats() && self.float((x, y)) {
+                    if watched {
+                        self.trace.push("floating up".to_string());
+                    }
+                    continue;
+                }
+
+                if watched {
+                    self.trace
+                        .push(format!("running the rules for {}", kind.name()));
+                }
+                let element = self.particles.get((x, y)).kind().element();
+                element.update(&mut CellCtx {
+                    world: self,
+                    pos: (x, y),
+                    rng: &mut rng,
+                });
+            }
+        }
+        rng
+    }
+
+    /// Every cell whose particle isn't exactly the same in `other`
+    pub fn differences(&self, other: &World) -> Vec<(usize, usize)> {
+        let width = self.width();
+        (0..self.height())
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&pos| self.particles.get(pos) != other.particles.get(pos))
+            .collect()
+    }
+
+    /// Builds a candle standing on `(x, y)`: a column of wax with a wick running
+    /// down the middle and poking out of the top
+    pub fn place_candle(&mut self, (x, y): (usize, usize)) {
+        let top = y.saturating_sub(CANDLE_HEIGHT);
+        for cy in top..=y {
+            for cx in x.saturating_sub(1)..=x + 1 {
+                if cx == x {
+                    self.set_pixel((cx, cy), Kind::Wick);
+                } else if cy != top {
+                    self.set_pixel((cx, cy), Kind::Wax);
+                }
+            }
+        }
+    }
+
+    /// Sends a meteor down from a random spot on the top edge
+    pub fn spawn_meteor(&mut self) {
+        let x = self.rng.gen_range(0..self.width());
+        let drift = self.rng.gen_range(0..=1);
+        self.replace(
+            (x, 0),
+            Particle::new(Kind::Meteor)
+                .with_turn(self.turn())
+                .with_counter(drift),
+        );
+    }
+
+    // Ants fall until they land, then walk along whatever they're on, climbing
+    // over small steps and tunnelling into sand. Their counter is which way
+    // they're heading: 0 for left and 1 for right.
+    fn walk_ant(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) {
+        if rng.gen_ratio(1, ANT_DROWN_ODDS)
+            && self
+                .find_neighbor((x, y), |p| p.kind() == Kind::Water)
+                .is_some()
+        {
+            self.replace((x, y), Particle::default());
+            return;
+        }
+
+        if let Some(below) = self.offset((x, y), (0, 1)) {
+            if Kind::Ant.sinks_through(self.particles.get(below).kind()) {
+                self.swap((x, y), below);
+                return;
+            }
+        }
+        if !rng.gen_ratio(1, ANT_STEP_ODDS) {
+            return;
+        }
+        if rng.gen_ratio(1, ANT_TURN_ODDS) {
+            self.modify((x, y), |p| p.counter ^= 1);
+            return;
+        }
+
+        let facing = if self.particles.get((x, y)).counter == 0 {
+            -1
+        } else {
+            1
+        };
+        let Some(ahead) = self.offset((x, y), (facing, 0)) else {
+            self.modify((x, y), |p| p.counter ^= 1);
+            return;
+        };
+        let step = self
+            .offset((x, y), (facing, -1))
+            .filter(|&step| self.particles.get(step).empty());
+        let in_front = self.particles.get(ahead).kind();
+        if in_front == Kind::Empty {
+            self.move_particle((x, y), ahead);
+        } else if let Some(step) = step {
+            // Climb up onto the step
+            self.move_particle((x, y), step);
+        } else if in_front == Kind::Sand {
+            // Tunnel in, pushing the sand back behind
+            if rng.gen_ratio(1, ANT_DIG_ODDS) {
+                self.swap((x, y), ahead);
+            }
+        } else {
+            self.modify((x, y), |p| p.counter ^= 1);
+        }
+    }
+
+    // Whether a sticky particle holds on this tick, to a solid beside or
+    // above it, or to more of itself above in a drip. Only particles with
+    // nothing under them but more of themselves need to.
+    fn clings(&self, (x, y): (usize, usize), rng: &mut impl Rng) -> bool {
+        let kind = self.particles.get((x, y)).kind();
+        let adhesion = kind.adhesion();
+        let Some(below) = self.offset((x, y), (0, 1)).filter(|_| adhesion > 0) else {
+            return false;
+        };
+        let below = self.particles.get(below).kind();
+        if below != kind && !kind.sinks_through(below) {
+            return false;
+        }
+        let on_wall = [(-1, 0), (1, 0), (0, -1)]
+            .into_iter()
+            .filter_map(|offset| self.offset((x, y), offset))
+            .any(|pos| self.particles.get(pos).kind().is_solid());
+        let in_drip = self
+            .offset((x, y), (0, -1))
+            .is_some_and(|above| self.particles.get(above).kind() == kind);
+        let grip = if on_wall {
+            adhesion
+        } else if in_drip {
+            (adhesion / DRIP_WEAKNESS).max(1)
+        } else {
+            return false;
+        };
+        !rng.gen_ratio(1, grip)
+    }
+
+    // Powders touching tar are stuck in it and can't move
+    fn stuck(&self, pos: (usize, usize)) -> bool {
+        self.find_neighbor(pos, |p| p.kind() == Kind::Tar).is_some()
+    }
+
+    // Lets out all the water a sponge is holding into the empty cells nearest
+    // to it, as far as SPONGE_CAPACITY cells away. Whatever doesn't fit is lost.
+    fn wring_sponge(&mut self, (x, y): (usize, usize)) {
+        let mut held = self.particles.get((x, y)).counter;
+        self.modify((x, y), |p| p.counter = 0);
+        for radius in 1..=SPONGE_CAPACITY as i32 {
+            for y_off in -radius..=radius {
+                for x_off in -radius..=radius {
+                    if held == 0 {
+                        return;
+                    }
+                    if let Some(pos) = self
+                        .offset((x, y), (x_off, y_off))
+                        .filter(|&pos| self.particles.get(pos).empty())
+                    {
+                        self.set_pixel(pos, Kind::Water);
+                        held -= 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // Moves a lit firework up to FIREWORK_SPEED cells, through empty cells and
+    // gases, and bursts it into embers once its flight is over
+    fn launch_firework(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) {
+        let mut fy = y;
+        for _ in 0..FIREWORK_SPEED {
+            if self.offset((x, fy), (0, -1)).is_none() {
+                break;
+            }
+            let above = self.particles.get((x, fy - 1)).kind();
+            if above != Kind::Empty && !above.is_gas() {
+                break;
+            }
+            self.swap((x, fy), (x, fy - 1));
+            fy -= 1;
+        }
+
+        let firework = self.particles.get((x, fy));
+        if fy != y && firework.counter < FIREWORK_FLIGHT_TICKS {
+            self.modify((x, fy), |p| p.counter += 1);
+            return;
+        }
+
+        // Blocked or out of fuel, so burst into a ball of embers
+        for y_off in -FIREWORK_BURST_RADIUS..=FIREWORK_BURST_RADIUS {
+            for x_off in -FIREWORK_BURST_RADIUS..=FIREWORK_BURST_RADIUS {
+                if x_off.pow(2) + y_off.pow(2) > FIREWORK_BURST_RADIUS.pow(2) {
+                    continue;
+                }
+                let Some(pos) = self.offset((x, fy), (x_off, y_off)) else {
+                    continue;
+                };
+                if pos == (x, fy) || (self.particles.get(pos).empty() && rng.gen_ratio(1, 3)) {
+                    self.replace(
+                        pos,
+                        Particle::new(Kind::Ember)
+                            .with_turn(self.turn())
+                            .with_stored(firework.stored()),
+                    );
+                }
+            }
+        }
+    }
+
+    // Blows a crater of `radius` into everything that isn't blast resistant,
+    // leaving fire, smoke and flying debris behind, and sends a shockwave of
+    // `power` out from its edge
+    fn explode(&mut self, (cx, cy): (i32, i32), radius: i32, power: i32, rng: &mut impl Rng) {
+        let center = (
+            cx.clamp(0, self.width() as i32 - 1) as usize,
+            cy.clamp(0, self.height() as i32 - 1) as usize,
+        );
+        self.pressure.add(center, radius * BLAST_PRESSURE);
+        let shockwave = Shockwave {
+            center: (cx, cy),
+            reach: radius,
+            power,
+        };
+        self.shockwaves.push(shockwave);
+
+        for y in (cy - radius)..=(cy + radius) {
+            for x in (cx - radius)..=(cx + radius) {
+                if (x - cx).pow(2) + (y - cy).pow(2) > radius.pow(2) {
+                    continue;
+                }
+                let from_center = (x - center.0 as i32, y - center.1 as i32);
+                let Some(pos) = self.offset(center, from_center) else {
+                    continue;
+                };
+                let kind = self.particles.get(pos).kind();
+                if kind.blast_resistant() {
+                    continue;
+                }
+
+                // Some of what gets blown apart flies out as debris, and the
+                // rest is left burning, smoking or gone
+                let ejecta = if kind.is_solid() { kind.rubble() } else { kind };
+                let debris = if (kind.is_powder() || kind.is_solid())
+                    && ejecta != Kind::Empty
+                    && rng.gen_ratio(1, 4)
+                {
+                    Particle::new(ejecta)
+                        .with_turn(self.turn())
+                        .with_velocity(shockwave.velocity_at((x, y)))
+                } else {
+                    let kind = match rng.gen_range(0..6) {
+                        0 | 1 => Kind::Fire,
+                        2 => Kind::Smoke,
+                        _ => Kind::Empty,
+                    };
+                    Particle::new(kind).with_turn(self.turn())
+                };
+                self.replace(pos, debris);
+            }
+        }
+    }
+
+    // Pushes each shockwave another SHOCKWAVE_SPEED cells out, throwing the
+    // powders and liquids it passes over, and lets it weaken until it dies out
+    fn propagate_shockwaves(&mut self) {
+        let mut shockwaves = std::mem::take(&mut self.shockwaves);
+        for shockwave in &mut shockwaves {
+            let (cx, cy) = shockwave.center;
+            let origin = (
+                cx.clamp(0, self.width() as i32 - 1) as usize,
+                cy.clamp(0, self.height() as i32 - 1) as usize,
+            );
+            let (inner, outer) = (shockwave.reach, shockwave.reach + SHOCKWAVE_SPEED);
+            for y in (cy - outer)..=(cy + outer) {
+                for x in (cx - outer)..=(cx + outer) {
+                    let distance = (x - cx).pow(2) + (y - cy).pow(2);
+                    if distance <= inner.pow(2) || distance > outer.pow(2) {
+                        continue;
+                    }
+                    let from_origin = (x - origin.0 as i32, y - origin.1 as i32);
+                    let Some(pos) = self.offset(origin, from_origin) else {
+                        continue;
+                    };
+                    let kind = self.particles.get(pos).kind();
+                    if kind.is_powder() || kind.is_liquid() {
+                        let velocity = shockwave.velocity_at((x, y));
+                        self.modify(pos, |p| p.velocity = velocity);
+                    }
+                }
+            }
+            shockwave.reach = outer;
+            shockwave.power -= 1;
+        }
+        shockwaves.retain(|shockwave| shockwave.power > 0);
+        self.shockwaves = shockwaves;
+    }
+
+    // A particle thrown upwards flies along its velocity, slowing as gravity
+    // pulls it back, until it runs into something or starts to fall again.
+    // Returns whether it moved.
+    fn fly(&mut self, (x, y): (usize, usize)) -> bool {
+        let (vx, vy) = self.particles.get((x, y)).velocity;
+        let steps = vx.unsigned_abs().max(vy.unsigned_abs()) as i32;
+        let mut at = (x, y);
+        let mut blocked = false;
+        for n in 1..=steps {
+            // Rounding the rise up lifts it off before it goes sideways
+            let offset = (vx as i32 * n / steps, (vy as i32 * n).div_euclid(steps));
+            let Some(next) = self.offset((x, y), offset) else {
+                if self.fall_off(at) {
+                    return true;
+                }
+                blocked = true;
+                break;
+            };
+            if !self.particles.get(next).empty() {
+                blocked = true;
+                break;
+            }
+            self.move_particle(at, next);
+            at = next;
+        }
+        let velocity = if !blocked { (vx, vy + 1) } else { (0, 0) };
+        self.modify(at, |p| p.velocity = velocity);
+        at != (x, y)
+    }
+
+    // Soaks an absorbent particle right through next to water, or part of
+    // the way from a wetter neighbor, or else lets it dry out a little,
+    // quicker next to heat
+    fn soak(&mut self, pos: (usize, usize), rng: &mut impl Rng) {
+        let wetness = self.particles.get(pos).wetness();
+        let soaked_up = if self
+            .find_neighbor(pos, |p| matches!(p.kind(), Kind::Water | Kind::Saltwater))
+            .is_some()
+        {
+            MAX_WETNESS
+        } else if let Some(wetter) = self
+            .find_neighbor(pos, |p| p.kind().absorbent() && p.wetness() > wetness + 1)
+            .filter(|_| rng.gen_ratio(1, SOAK_ODDS))
+        {
+            self.particles.get(wetter).wetness() - 1
+        } else {
+            0
+        };
+
+        if soaked_up > wetness {
+            self.modify(pos, |p| p.set_wetness(soaked_up));
+        } else if wetness > 0 {
+            let odds = if self.find_neighbor(pos, |p| p.kind().is_hot()).is_some() {
+                HEATED_DRY_ODDS
+            } else {
+                DRY_ODDS
+            };
+            if rng.gen_ratio(1, odds) {
+                self.modify(pos, |p| p.set_wetness(p.wetness() - 1));
+            }
+        }
+    }
+
+    // Catches a flammable particle from heat next to it, and burns a lit one
+    // down, giving off flames as it goes. Returns whether it burned up.
+    fn burn(&mut self, pos: (usize, usize), rng: &mut impl Rng) -> bool {
+        let particle = self.particles.get(pos);
+        let flammability = particle.kind().flammability();
+        let burn_time = particle.kind().burn_time();
+        if burn_time == 0 || !particle.burning() {
+            // It has to dry out before it will catch
+            if !particle.wet()
+                && rng.gen_ratio(flammability, FLAMMABILITY_SCALE)
+                && self.find_neighbor(pos, |p| p.kind().is_hot()).is_some()
+            {
+                self.ignite(pos);
+                return burn_time == 0;
+            }
+            return false;
+        }
+
+        if particle.counter >= burn_time {
+            self.replace(
+                pos,
+                Particle::new(particle.kind().burn_product()).with_turn(self.turn()),
+            );
+            return true;
+        }
+
+        if rng.gen_ratio(1, BURN_RATE) {
+            self.modify(pos, |p| p.counter += 1);
+        }
+        if rng.gen_ratio(flammability, FLAMMABILITY_SCALE) {
+            if let Some(flame) = self.find_neighbor(pos, |p| p.empty()) {
+                self.replace(flame, Particle::new(Kind::Fire).with_turn(self.turn()));
+            }
+        }
+        false
+    }
+
+    // Sets a flammable particle alight, burning it up at once if it doesn't
+    // take any time to burn
+    fn ignite(&mut self, pos: (usize, usize)) {
+        let kind = self.particles.get(pos).kind();
+        if kind.burn_time() == 0 {
+            self.replace(
+                pos,
+                Particle::new(kind.burn_product()).with_turn(self.turn()),
+            );
+        } else {
+            self.modify(pos, |p| p.set_burning(true));
+        }
+    }
+
+    // Turns the whole pocket of gas connected to `start` into fire at once
+    fn ignite_pocket(&mut self, start: (usize, usize)) {
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            if self.particles.get((x, y)).kind() != Kind::Gas {
+                continue;
+            }
+            self.replace((x, y), Particle::new(Kind::Fire).with_turn(self.turn()));
+            for y_off in -1..=1 {
+                for x_off in -1..=1 {
+                    stack.extend(self.offset((x, y), (x_off, y_off)));
+                }
+            }
+        }
+    }
+
+    /// Rains the given kind down from a span of the top edge
+    pub fn pour(&mut self, kind: Kind, span: std::ops::Range<usize>) {
+        for x in span {
+            if self.rng.gen_ratio(1, 3) {
+                self.set_pixel((x, 0), kind);
+            }
+        }
+    }
+
+    /// The empty space connected to `start` at or below its height,
+    /// i.e. what a liquid poured in at `start` would fill
+    pub fn basin(&self, start: (usize, usize)) -> Vec<(usize, usize)> {
+        let level = start.1;
+        let mut visited = vec![false; self.width() * self.height()];
+        let mut cells = Vec::new();
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            let i = y * self.width() + x;
+            if visited[i] || !self.particles.get((x, y)).empty() {
+                continue;
+            }
+            visited[i] = true;
+            cells.push((x, y));
+
+            // Only up as far as the level it's filled to
+            let up = (y > level).then_some((0, -1));
+            for offset in [(-1, 0), (1, 0), (0, 1)].into_iter().chain(up) {
+                stack.extend(self.offset((x, y), offset));
+            }
+        }
+        cells
+    }
+
+    /// Floods the empty space connected to `start` with a liquid, up to the
+    /// height of `start`
+    pub fn fill_to_level(&mut self, start: (usize, usize), kind: Kind) {
+        if !kind.is_liquid() {
+            return;
+        }
+
+        for pos in self.basin(start) {
+            self.set_pixel(pos, kind);
+        }
+    }
+
+    /// Turns every particle of one kind into another, optionally only inside
+    /// the inclusive rectangle between two corners
+    pub fn replace_kind(
+        &mut self,
+        from: Kind,
+        to: Kind,
+        region: Option<((usize, usize), (usize, usize))>,
+    ) {
+        let ((x0, y0), (x1, y1)) =
+            region.unwrap_or(((0, 0), (self.width() - 1, self.height() - 1)));
+        for y in y0.min(y1)..=y0.max(y1) {
+            for x in x0.min(x1)..=x0.max(x1) {
+                if self.particles.get((x, y)).kind() == from {
+                    self.replace((x, y), Particle::new(to).with_turn(self.turn()));
+                }
+            }
+        }
+    }
+
+    // Stamps every particle in the chunks that have just woken as having had
+    // the last turn, whichever one they really had before they slept, so none
+    // of them is taken to have had this one already
+    fn restamp_woken_chunks(&mut self) {
+        let (width, height) = (self.width(), self.height());
+        let last = Turn(!self.turn().0);
+        for &(x0, y0) in self.chunks.woken() {
+            for y in y0..(y0 + CHUNK_SIZE).min(height) {
+                for x in x0..(x0 + CHUNK_SIZE).min(width) {
+                    self.particles
+                        .modify((x, y), |particle| particle.set_turn(last));
+                }
+            }
+        }
+    }
+
+    // Whether a cell's chunk has settled and is skipped this tick
+    fn asleep(&self, pos: (usize, usize)) -> bool {
+        match &self.strip {
+            // A strip is a single column of chunks, so only how far down matters
+            Some(strip) => !strip.awake[pos.1 / CHUNK_SIZE],
+            None => self.sleeping && !self.chunks.awake(pos),
+        }
+    }
+
+    // The parts of `span` along row `y` that aren't in a sleeping chunk, one
+    // for each chunk it crosses
+    fn awake_runs(
+        &self,
+        y: usize,
+        span: std::ops::Range<usize>,
+    ) -> impl DoubleEndedIterator<Item = std::ops::Range<usize>> + '_ {
+        (span.start / CHUNK_SIZE..span.end.div_ceil(CHUNK_SIZE))
+            .map(move |chunk| {
+                (chunk * CHUNK_SIZE).max(span.start)..((chunk + 1) * CHUNK_SIZE).min(span.end)
+            })
+            .filter(move |run| !self.asleep((run.start, y)))
+    }
+
+    // Wakes the chunks under any wind or strong enough pressure wave, since
+    // either could stir up what has settled there
+    fn stir_chunks(&mut self) {
+        for y in (0..self.height()).step_by(WIND_BLOCK_SIZE) {
+            for x in (0..self.width()).step_by(WIND_BLOCK_SIZE) {
+                if self.wind.at((x, y)) != (0, 0) {
+                    self.chunks.touch((x, y));
+                }
+            }
+        }
+        for y in (0..self.height()).step_by(PRESSURE_BLOCK_SIZE) {
+            for x in (0..self.width()).step_by(PRESSURE_BLOCK_SIZE) {
+                let (px, py) = self.pressure.push((x, y));
+                if px.abs().max(py.abs()) >= PRESSURE_PUSH_THRESHOLD {
+                    self.chunks.touch((x, y));
+                }
+            }
+        }
+    }
+
+    // Slow kinds, and far away from the focus whole blocks, take turns
+    // updating at a coarser rate
+    fn due_for_update(&self, pos: (usize, usize)) -> bool {
+        let stride = self.particles.get(pos).kind().update_stride();
+        let (x, y) = self.absolute(pos);
+        if stride > 1
+            && !self
+                .tick
+                .wrapping_add((x + y) as u32)
+                .is_multiple_of(stride)
+        {
+            return false;
+        }
+
+        if !self.lod {
+            return true;
+        }
+
+        let (block_x, block_y) = (x / LOD_BLOCK_SIZE, y / LOD_BLOCK_SIZE);
+        let (focus_x, focus_y) = (
+            self.lod_focus.0 / LOD_BLOCK_SIZE,
+            self.lod_focus.1 / LOD_BLOCK_SIZE,
+        );
+        if block_x.abs_diff(focus_x) <= LOD_BLOCK_RADIUS
+            && block_y.abs_diff(focus_y) <= LOD_BLOCK_RADIUS
+        {
+            return true;
+        }
+
+        let width = self
+            .strip
+            .as_ref()
+            .map_or(self.width(), |strip| strip.world_width);
+        let stagger = (block_x + block_y * width / LOD_BLOCK_SIZE) as u32;
+        self.tick.wrapping_add(stagger).is_multiple_of(LOD_STRIDE)
+    }
+
+    /// Shakes the world for a while, weakening its structures
+    pub fn start_earthquake(&mut self) {
+        self.quake_ticks = QUAKE_TICKS;
+    }
+
+    // Jolts loose particles in random directions, biased upwards so that
+    // they land somewhere new and piles slump
+    fn shake(&mut self, rng: &mut impl Rng) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let kind = self.particles.get((x, y)).kind();
+                if !(kind.is_powder() || kind.is_liquid()) || !rng.gen_ratio(1, 6) {
+                    continue;
+                }
+
+                let jolt = (rng.gen_range(-1..=1), -rng.gen_range(0..=1));
+                if let Some(to) = self
+                    .offset((x, y), jolt)
+                    .filter(|&to| self.particles.get(to).empty())
+                {
+                    self.move_particle((x, y), to);
+                }
+            }
+        }
+    }
+
+    // Lets the air pressure spread and leak, topped up by every gas particle
+    fn update_pressure(&mut self) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if self.particles.get((x, y)).kind().is_gas() {
+                    self.pressure.add((x, y), GAS_PRESSURE);
+                }
+            }
+        }
+        self.pressure.step(PRESSURE_LEAK);
+    }
+
+    // Slides a powder that can't fall off the side of its pile, towards a
+    // random side and as far over as its kind reaches, into the first spot
+    // with a deep enough drop. Returns whether it moved.
+    fn slip(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) -> bool {
+        let particle = self.particles.get((x, y));
+        let Some((reach, drop)) = particle.kind().slip() else {
+            return false;
+        };
+        // Wet powder clumps together instead
+        if particle.wetness() >= CLUMP_WETNESS {
+            return false;
+        }
+        if self.offset((x, y), (0, drop as i32)).is_none() {
+            return false;
+        }
+        let sign = rng.gen::<bool>() as i32 * 2 - 1;
+        for n in 1..=reach {
+            let Some((nx, _)) = self.offset((x, y), (n * sign, 0)) else {
+                return self.fall_off((x, y));
+            };
+            if (y + 1..=y + drop)
+                .all(|dy| particle.sinks_through(self.particles.get((nx, dy)).kind()))
+            {
+                self.swap((x, y), (nx, y + 1));
+                return true;
+            }
+            // Reaching further means passing over this cell
+            if !self.particles.get((nx, y)).empty() {
+                return false;
+            }
+        }
+        false
+    }
+
+    // Moves a light enough particle a cell along with the wind, with odds in
+    // proportion to how hard it's blowing each way. Returns whether it moved.
+    fn carry_by_wind(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) -> bool {
+        let particle = self.particles.get((x, y));
+        let Some(resistance) = particle.kind().wind_resistance() else {
+            return false;
+        };
+        // Soaked up water weighs it down
+        let resistance = resistance + particle.wetness() as i32;
+        let (vx, vy) = self.wind.at((x, y));
+        let mut step = |v: i32| {
+            let odds = (v.unsigned_abs()).min((WIND_SCALE * resistance) as u32);
+            if odds > 0 && rng.gen_ratio(odds, (WIND_SCALE * resistance) as u32) {
+                v.signum()
+            } else {
+                0
+            }
+        };
+        let Some(to) = self.offset((x, y), (step(vx), step(vy))) else {
+            return self.fall_off((x, y));
+        };
+        if !self.particles.get(to).empty() {
+            return false;
+        }
+        self.move_particle((x, y), to);
+        true
+    }
+
+    // Moves a particle a cell down the pressure gradient, if it's steep enough
+    // and the odds come up. Returns whether it moved.
+    fn blow(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) -> bool {
+        let (px, py) = self.pressure.push((x, y));
+        let strength = px.abs().max(py.abs());
+        if strength < PRESSURE_PUSH_THRESHOLD
+            || !rng.gen_ratio(
+                (strength as u32).min(PRESSURE_PUSH_SCALE),
+                PRESSURE_PUSH_SCALE,
+            )
+        {
+            return false;
+        }
+
+        let step = |push: i32| {
+            if push.abs() * 2 >= strength {
+                push.signum()
+            } else {
+                0
+            }
+        };
+        let Some(to) = self.offset((x, y), (step(px), step(py))) else {
+            return self.fall_off((x, y));
+        };
+        let kind = self.particles.get((x, y)).kind();
+        if !kind.sinks_through(self.particles.get(to).kind()) {
+            return false;
+        }
+        self.swap((x, y), to);
+        true
+    }
+
+    // Spreads heat between neighboring particles, pins heat sources at their
+    // temperatures, and changes the state of whatever has crossed one of its
+    // kind's melting, boiling or freezing points. Fractions of a degree are
+    // rounded up or down at random in proportion, so small differences still
+    // even out over time.
+    fn conduct_heat(&mut self, rng: &mut impl Rng) {
+        let (width, height) = (self.width(), self.height());
+        let mut before = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let particle = self.particles.get((x, y));
+                before.push((particle.kind(), particle.temperature));
+            }
+        }
+
+        for y in 0..height {
+            let runs: Vec<_> = self.awake_runs(y, 0..width).collect();
+            for x in runs.into_iter().flatten() {
+                let (kind, temperature) = before[y * width + x];
+                if kind == Kind::Empty {
+                    continue;
+                }
+                let temperature = if let Some(fixed) = kind.fixed_temperature() {
+                    fixed
+                } else {
+                    let mut flow = 0;
+                    for (x_off, y_off) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                        let Some((nx, ny)) = self.offset((x, y), (x_off, y_off)) else {
+                            let edge = Edge::beyond((width, height), (x, y), (x_off, y_off));
+                            if let Some(edge_temperature) =
+                                edge.and_then(|edge| self.edge_temperatures[edge as usize])
+                            {
+                                flow +=
+                                    (edge_temperature - temperature) as i32 * kind.conductivity();
+                            }
+                            continue;
+                        };
+                        let (other, other_temperature) = before[ny * width + nx];
+                        let (other_temperature, conductivity) = if other == Kind::Empty {
+                            (AMBIENT_TEMPERATURE, AIR_CONDUCTIVITY)
+                        } else {
+                            (
+                                other_temperature,
+                                kind.conductivity().min(other.conductivity()),
+                            )
+                        };
+                        flow += (other_temperature - temperature) as i32 * conductivity;
+                    }
+                    let mut change = flow.div_euclid(CONDUCTION_SCALE);
+                    if rng.gen_range(0..CONDUCTION_SCALE) < flow.rem_euclid(CONDUCTION_SCALE) {
+                        change += 1;
+                    }
+                    temperature.saturating_add(change as i16)
+                };
+                // Warming up or cooling down keeps a chunk awake
+                if temperature != before[y * width + x].1 {
+                    self.chunks.touch((x, y));
+                }
+                self.particles
+                    .modify((x, y), |p| p.temperature = temperature);
+
+                let Some(changes_to) = kind.state_change(temperature) else {
+                    continue;
+                };
+                self.replace((x, y), self.particles.get((x, y)).with_kind(changes_to));
+            }
+        }
+    }
+
+    // Carries every fresh spark through all the conductors connected to it at
+    // once, so that a long wire lights up from end to end in a single tick.
+    // Conductors still resting from the last spark don't pass it on.
+    fn conduct_charge(&mut self) {
+        let mut queue = VecDeque::new();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let particle = self.particles.get((x, y));
+                if particle.kind() == Kind::Spark && particle.counter == SPARK_LIFE {
+                    queue.push_back((x, y));
+                }
+            }
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            for (x_off, y_off) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                let Some(pos) = self.offset((x, y), (x_off, y_off)) else {
+                    continue;
+                };
+                let neighbor = self.particles.get(pos);
+                if neighbor.kind().conducts() && neighbor.counter == 0 {
+                    self.replace(
+                        pos,
+                        Particle::new(Kind::Spark)
+                            .with_turn(neighbor.turn())
+                            .with_counter(SPARK_LIFE)
+                            .with_stored(neighbor.kind())
+                            .with_temperature(neighbor.temperature),
+                    );
+                    queue.push_back(pos);
+                }
+            }
+        }
+    }
+
+    // Finds each connected body of runny liquid and moves particles from the
+    // top of its highest surface into the lowest empty cells that it borders
+    // and that something holds up, the way pressure would push it through
+    fn level_liquids(&mut self, rng: &mut impl Rng) {
+        let (width, height) = (self.width(), self.height());
+        let mut visited = vec![false; width * height];
+        for start_y in 0..height {
+            let runs: Vec<_> = self.awake_runs(start_y, 0..width).collect();
+            for start_x in runs.into_iter().flatten() {
+                let kind = self.particles.get((start_x, start_y)).kind();
+                if visited[start_y * width + start_x] || !kind.is_liquid() || kind.viscosity() > 1 {
+                    continue;
+                }
+
+                let mut surfaces = Vec::new();
+                let mut openings = Vec::new();
+                let mut stack = vec![(start_x, start_y)];
+                visited[start_y * width + start_x] = true;
+                while let Some((x, y)) = stack.pop() {
+                    if self
+                        .offset((x, y), (0, -1))
+                        .is_some_and(|above| self.particles.get(above).empty())
+                    {
+                        surfaces.push((x, y));
+                    }
+                    for offset in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                        let Some((nx, ny)) = self.offset((x, y), offset) else {
+                            continue;
+                        };
+                        if visited[ny * width + nx] {
+                            continue;
+                        }
+                        let neighbor = self.particles.get((nx, ny)).kind();
+                        if neighbor == kind {
+                            visited[ny * width + nx] = true;
+                            stack.push((nx, ny));
+                        } else if neighbor == Kind::Empty
+                            && self
+                                .offset((nx, ny), (0, 1))
+                                .is_none_or(|below| !self.particles.get(below).empty())
+                        {
+                            visited[ny * width + nx] = true;
+                            openings.push((nx, ny));
+                        }
+                    }
+                }
+                // Openings are only marked so they're counted once per body
+                for &(x, y) in &openings {
+                    visited[y * width + x] = false;
+                }
+
+                // Cells at the same height go in random order, since the search
+                // finds the ones off to one side first
+                surfaces.shuffle(rng);
+                openings.shuffle(rng);
+                surfaces.sort_by_key(|&(_, y)| y);
+                openings.sort_by_key(|&(_, y)| std::cmp::Reverse(y));
+                for (&from, &to) in surfaces.iter().zip(&openings).take(LEVEL_TRANSFERS) {
+                    if to.1 <= from.1 + 1 {
+                        break;
+                    }
+                    self.move_particle(from, to);
+                }
+            }
+        }
+    }
+
+    // Measures how far each solid cell reaches sideways from a supported cell
+    // of its structure, and cracks the cells that reach further than their
+    // material can hold. Moving vertically through a structure is free, so
+    // towers stand while long bridges and ledges eventually give way.
+    fn check_integrity(&mut self, rng: &mut impl Rng) {
+        let width = self.width();
+        let height = self.height();
+
+        let mut overhang = vec![usize::MAX; width * height];
+        let mut queue = VecDeque::new();
+        for y in 0..height {
+            for x in 0..width {
+                if self.particles.get((x, y)).kind().is_solid()
+                    && self
+                        .offset((x, y), (0, 1))
+                        .is_none_or(|below| self.particles.get(below).kind().is_powder())
+                {
+                    overhang[y * width + x] = 0;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            let dist = overhang[y * width + x];
+            for (x_off, y_off) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                let Some((nx, ny)) = self
+                    .offset((x, y), (x_off, y_off))
+                    .filter(|&pos| self.particles.get(pos).kind().is_solid())
+                else {
+                    continue;
+                };
+                let new_dist = dist + x_off.unsigned_abs() as usize;
+                if new_dist < overhang[ny * width + nx] {
+                    overhang[ny * width + nx] = new_dist;
+                    if x_off == 0 {
+                        queue.push_front((nx, ny));
+                    } else {
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+
+        let stress = if self.quake_ticks > 0 {
+            QUAKE_STRESS_DIVISOR
+        } else {
+            1
+        };
+        let mut loose = HashMap::new();
+        for y in 0..height {
+            // Only cells in awake chunks can give way; the search above still
+            // runs through settled ones, since they hold up the rest
+            let runs: Vec<_> = self.awake_runs(y, 0..width).collect();
+            for x in runs.into_iter().flatten() {
+                let particle = self.particles.get((x, y));
+                // Structures with nothing at all holding them up drop in one
+                // piece instead, unless part of them holds itself up
+                if overhang[y * width + x] == usize::MAX {
+                    if let Some(id) = self.components.component_at((x, y)) {
+                        if *loose.entry(id).or_insert_with(|| !self.anchored((x, y))) {
+                            continue;
+                        }
+                    }
+                }
+                // Kinds that hold themselves up don't weaken in a quake either
+                let max_overhang = match particle.kind().max_overhang() {
+                    usize::MAX => usize::MAX,
+                    max_overhang => max_overhang / stress,
+                };
+                if particle.kind().is_solid()
+                    && overhang[y * width + x] > max_overhang
+                    && rng.gen_ratio(1, 4)
+                {
+                    if particle.kind() == Kind::Sponge {
+                        self.wring_sponge((x, y));
+                    }
+                    self.replace(
+                        (x, y),
+                        self.particles
+                            .get((x, y))
+                            .with_kind(particle.kind().rubble()),
+                    );
+                }
+            }
+        }
+    }
+
+    // Relabels every structure from scratch, for when the boundary changes
+    // which cells count as neighbors
+    fn rebuild_components(&mut self) {
+        self.components = Components::new((self.width(), self.height()), self.boundary);
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if self.particles.get((x, y)).kind().is_solid() {
+                    self.components.insert((x, y));
+                }
+            }
+        }
+    }
+
+    // Whether any part of the structure at `pos` is of a kind that holds
+    // itself up
+    fn anchored(&self, pos: (usize, usize)) -> bool {
+        self.components
+            .component_cells(pos)
+            .into_iter()
+            .any(|pos| self.particles.get(pos).kind().max_overhang() == usize::MAX)
+    }
+
+    // Drops every structure with nothing under it by a cell, all in one
+    // piece, through anything its cells would sink through. Whatever it falls
+    // into ends up on top of it. Structures that are partly made of something
+    // that holds itself up stay put.
+    fn drop_loose_structures(&mut self) {
+        let (width, height) = (self.width(), self.height());
+        let mut visited = vec![false; width * height];
+        for y in (0..height).rev() {
+            for x in 0..width {
+                if visited[y * width + x] || self.components.component_at((x, y)).is_none() {
+                    continue;
+                }
+                let cells = self.components.component_cells((x, y));
+                for &(cx, cy) in &cells {
+                    visited[cy * width + cx] = true;
+                }
+                let body: HashSet<(usize, usize)> = cells.iter().copied().collect();
+                let loose = cells.iter().all(|&(cx, cy)| {
+                    let kind = self.particles.get((cx, cy)).kind();
+                    kind.max_overhang() != usize::MAX
+                        && self.offset((cx, cy), (0, 1)).is_some_and(|below| {
+                            body.contains(&below)
+                                || kind.sinks_through(self.particles.get(below).kind())
+                        })
+                });
+                if !loose {
+                    continue;
+                }
+
+                // Shift each vertical run of the structure down, from its bottom
+                let bottoms = cells
+                    .iter()
+                    .filter(|&&(cx, cy)| !body.contains(&(cx, cy + 1)));
+                for &(bx, by) in bottoms {
+                    let mut top = by;
+                    while let Some(above) = self
+                        .offset((bx, top), (0, -1))
+                        .filter(|above| body.contains(above))
+                    {
+                        top = above.1;
+                    }
+                    for ry in (top..=by).rev() {
+                        self.swap((bx, ry), (bx, ry + 1));
+                        visited[(ry + 1) * width + bx] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Falls straight down, a cell further each tick than the last up to
+    // MAX_FALL_SPEED. Only the first cell can be through something it sinks
+    // through, the rest have to be empty. Returns where the particle ended up
+    // if it fell at all.
+    fn fall(&mut self, (x, y): (usize, usize)) -> Option<(usize, usize)> {
+        let particle = self.particles.get((x, y));
+        let speed = particle
+            .velocity
+            .1
+            .saturating_add(1)
+            .clamp(1, MAX_FALL_SPEED) as usize;
+        if self.offset((x, y), (0, 1)).is_none() {
+            self.fall_off((x, y));
+            return None;
+        }
+        let mut fy = y;
+        while fy - y < speed && self.offset((x, fy), (0, 1)).is_some() {
+            let below = self.particles.get((x, fy + 1)).kind();
+            if below != Kind::Empty && (fy != y || !particle.sinks_through(below)) {
+                break;
+            }
+            self.swap((x, fy), (x, fy + 1));
+            fy += 1;
+            if below != Kind::Empty {
+                break;
+            }
+        }
+        if fy == y {
+            return None;
+        }
+        self.modify((x, fy), |p| p.velocity = (0, (fy - y) as i8));
+        Some((x, fy))
+    }
+
+    // Moves a particle up through the liquid above it if it's lighter.
+    // Returns whether it moved.
+    fn float(&mut self, (x, y): (usize, usize)) -> bool {
+        if self.offset((x, y), (0, -1)).is_none() {
+            return false;
+        }
+        let above = self.particles.get((x, y - 1)).kind();
+        if !above.is_liquid() || !self.particles.get((x, y)).rises_through(above) {
+            return false;
+        }
+        self.swap((x, y), (x, y - 1));
+        true
+    }
+
+    // A particle that has landed hard splashes off to one side, sliding
+    // along the ground and slowing down as it goes. Returns whether it moved.
+    fn splash(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) -> bool {
+        let (vx, vy) = self.particles.get((x, y)).velocity;
+        if vy >= SPLASH_SPEED {
+            self.impact((x, y), vy, rng);
+        }
+        let vx = if vy >= SPLASH_SPEED {
+            vy / 2 * (rng.gen::<bool>() as i8 * 2 - 1)
+        } else {
+            vx
+        };
+        let mut sx = x;
+        for _ in 0..vx.unsigned_abs() {
+            let Some(next) = self.offset((sx, y), (vx.signum() as i32, 0)) else {
+                if self.fall_off((sx, y)) {
+                    return true;
+                }
+                break;
+            };
+            if !self.particles.get(next).empty() {
+                break;
+            }
+            self.move_particle((sx, y), next);
+            sx = next.0;
+        }
+        let vx = if sx == x { 0 } else { vx - vx.signum() };
+        self.modify((sx, y), |p| p.velocity = (vx, 0));
+        sx != x
+    }
+
+    // Passes some of a hard landing's speed on to the powder it landed on,
+    // knocking the grains beneath it out to the sides and throwing the ones
+    // diagonally below up and away, so that impacts leave small craters
+    fn impact(&mut self, (x, y): (usize, usize), speed: i8, rng: &mut impl Rng) {
+        if self.offset((x, y), (0, 1)).is_none() {
+            return;
+        }
+        let center_sign = rng.gen::<bool>() as i8 * 2 - 1;
+        for x_off in [-1, 0, 1] {
+            let Some(pos) = self.offset((x, y), (x_off, 1)) else {
+                continue;
+            };
+            if !self.particles.get(pos).kind().is_powder() {
+                continue;
+            }
+            let (sign, rise) = if x_off == 0 {
+                (center_sign, 0)
+            } else {
+                (x_off as i8, speed / IMPACT_UPWARDS_SHARE)
+            };
+            self.modify(pos, |p| {
+                p.velocity = (sign * speed / IMPACT_SIDEWAYS_SHARE, -rise)
+            });
+        }
+    }
+
+    // Wanders at random, drifting up a little more often than not if lighter
+    // than air, or down if heavier, and spreading out sideways as far as the
+    // gas disperses. Every gas moves this way except helium, which races up.
+    fn drift_gas(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) {
+        if !rng.gen_ratio(1, GAS_DRIFT_ODDS) {
+            return;
+        }
+        let kind = self.particles.get((x, y)).kind();
+        let buoyancy = (kind.density() as i32 - AIR_DENSITY as i32).signum();
+        let dy = if buoyancy != 0 && rng.gen_ratio(1, 3) {
+            buoyancy
+        } else {
+            rng.gen_range(-1..=1)
+        };
+        let dispersion = kind.dispersion();
+        let dx = rng.gen_range(-dispersion..=dispersion);
+        let Some((nx, ny)) = self.offset((x, y), (dx, dy)) else {
+            self.fall_off((x, y));
+            return;
+        };
+        // Anything in the way stops it from wandering further than a cell
+        if (1..dx.abs()).any(|i| {
+            self.offset((x, y), (i * dx.signum(), dy))
+                .is_none_or(|pos| !self.particles.get(pos).empty())
+        }) {
+            return;
+        }
+        // Light gases bubble up through heavier fluids, and heavy ones settle
+        // beneath lighter ones
+        let other = self.particles.get((nx, ny)).kind();
+        let displaces = match dy {
+            -1 if buoyancy < 0 => kind.rises_through(other),
+            1 if buoyancy > 0 => kind.sinks_through(other),
+            _ => other == Kind::Empty,
+        };
+        if !displaces {
+            return;
+        }
+
+        // Gas resists being squeezed into air that's already at higher pressure
+        let to = (nx, ny);
+        let squeeze = self.pressure.at(to) - self.pressure.at((x, y));
+        if squeeze > 0
+            && rng.gen_ratio(
+                (squeeze as u32).min(PRESSURE_PUSH_SCALE),
+                PRESSURE_PUSH_SCALE,
+            )
+        {
+            return;
+        }
+        if other == Kind::Empty {
+            self.move_particle((x, y), to);
+        } else {
+            self.swap((x, y), to);
+        }
+    }
+
+    // Helium races straight up, nudging light powders up ahead of it, and
+    // escapes off the top of the grid. Under a ceiling it spreads out sideways.
+    fn rise_helium(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) {
+        let mut hy = y;
+        for _ in 0..HELIUM_SPEED {
+            if self.offset((x, hy), (0, -1)).is_none() {
+                self.replace((x, hy), Particle::default());
+                return;
+            }
+            let above = self.particles.get((x, hy - 1)).kind();
+            if Kind::Helium.rises_through(above) {
+                self.swap((x, hy), (x, hy - 1));
+                hy -= 1;
+                continue;
+            }
+
+            if above.is_powder()
+                && above.density() <= HELIUM_LIFT_DENSITY
+                && self
+                    .offset((x, hy), (0, -2))
+                    .is_some_and(|pos| self.particles.get(pos).empty())
+                && rng.gen_ratio(1, HELIUM_LIFT_ODDS)
+            {
+                self.move_particle((x, hy - 1), (x, hy - 2));
+            }
+            break;
+        }
+        if hy != y {
+            return;
+        }
+
+        // Blocked, so try going around whatever is in the way
+        let Some((side, _)) = self.offset((x, y), (rng.gen::<bool>() as i32 * 2 - 1, 0)) else {
+            return;
+        };
+        if self
+            .offset((side, y), (0, -1))
+            .is_some_and(|pos| self.particles.get(pos).empty())
+        {
+            self.move_particle((x, y), (side, y - 1));
+        } else if self.particles.get((side, y)).empty() {
+            self.move_particle((x, y), (side, y));
+        }
+    }
+
+    // Falling for particles pulled some other way than straight down: along
+    // `down` when possible, otherwise diagonally down it, or sideways for
+    // liquids. `down` is a step to one of the four neighboring cells.
+    fn fall_along(&mut self, (x, y): (usize, usize), (dx, dy): (i32, i32), rng: &mut impl Rng) {
+        if (dx, dy) == (0, 0) {
+            return;
+        }
+        let kind = self.particles.get((x, y)).kind();
+        let sign = rng.gen::<bool>() as i32 * 2 - 1;
+        let (sx, sy) = (-dy * sign, dx * sign);
+        let ahead = self.offset((x, y), (dx, dy));
+        let diagonal = self.offset((x, y), (dx + sx, dy + sy));
+        let side = self.offset((x, y), (sx, sy));
+        if ahead.is_none() && self.fall_off((x, y)) {
+            return;
+        }
+        if let Some(ahead) =
+            ahead.filter(|&ahead| kind.sinks_through(self.particles.get(ahead).kind()))
+        {
+            self.swap((x, y), ahead);
+        } else if let Some(diagonal) =
+            diagonal.filter(|&diagonal| kind.sinks_through(self.particles.get(diagonal).kind()))
+        {
+            self.swap((x, y), diagonal);
+        } else if let Some(side) = side.filter(|&side| self.particles.get(side).empty()) {
+            if kind.is_liquid() {
+                self.move_particle((x, y), side);
+            }
+        }
+    }
+
+    // Sluggish flow for thick liquids: straight down when possible, otherwise
+    // spreading out like any other liquid. Returns where the particle ended up
+    // if it moved.
+    fn flow_viscous(
+        &mut self,
+        (x, y): (usize, usize),
+        rng: &mut impl Rng,
+    ) -> Option<(usize, usize)> {
+        let kind = self.particles.get((x, y)).kind();
+        match self.offset((x, y), (0, 1)) {
+            Some(below) if kind.sinks_through(self.particles.get(below).kind()) => {
+                self.swap((x, y), below);
+                return Some(below);
+            }
+            None if self.fall_off((x, y)) => return None,
+            _ => {}
+        }
+        self.spread_liquid((x, y), rng)
+    }
+
+    // Spreads out a liquid that can't fall, 1 in its viscosity ticks: down a
+    // cell or two diagonally into a pool, otherwise a cell to either side, or
+    // else skimming up to its spread across the top of the liquid beneath,
+    // stopping short of anything in the way. Returns where the particle ended
+    // up if it moved.
+    fn spread_liquid(
+        &mut self,
+        (x, y): (usize, usize),
+        rng: &mut impl Rng,
+    ) -> Option<(usize, usize)> {
+        let kind = self.particles.get((x, y)).kind();
+        if !rng.gen_ratio(1, kind.viscosity()) {
+            return None;
+        }
+        let spread = kind.spread();
+        let down_valid = self.offset((x, y), (0, 1)).is_some();
+
+        // Each hop is some number of cells to a random side. Hops sweep
+        // through every cell on the way, so they can't jump thin walls.
+        let mut hop = |cells: std::ops::RangeInclusive<i32>| {
+            (rng.gen_range(cells), rng.gen::<bool>() as i32 * 2 - 1)
+        };
+        let (diagonal, diagonal_sign) = hop(1..=spread.min(2));
+        let (_, side_sign) = hop(1..=1);
+        let (skim, skim_sign) = hop(2..=spread.max(2));
+
+        let empty = |world: &Self, pos: Option<(usize, usize)>| {
+            pos.is_some_and(|pos| world.particles.get(pos).empty())
+        };
+        // The cell before the one hopped to needs liquid underneath to slide along
+        let liquid_under = |world: &Self, pos: Option<(usize, usize)>| {
+            pos.and_then(|pos| world.offset(pos, (0, 1)))
+                .is_some_and(|under| world.particles.get(under).kind().is_liquid())
+        };
+
+        let (over, reached) = self.sweep((x, y), (diagonal_sign, 0), diagonal - 1);
+        let diagonal_to = self.offset(over, (diagonal_sign, 1));
+        let side_to = self.offset((x, y), (side_sign, 0));
+        let (skim_to, skimmed) = self.sweep((x, y), (skim_sign, 0), skim);
+        let to = if down_valid
+            && reached == diagonal - 1
+            && empty(self, diagonal_to)
+            && liquid_under(self, Some(over))
+        {
+            diagonal_to
+        } else if empty(self, side_to) {
+            side_to
+        } else if side_to.is_none() && self.fall_off((x, y)) {
+            return None;
+        } else if spread >= 2
+            && down_valid
+            && skimmed > 0
+            && liquid_under(self, self.offset(skim_to, (-skim_sign, 0)))
+        {
+            Some(skim_to)
+        } else {
+            None
+        }?;
+        self.move_particle((x, y), to);
+        Some(to)
+    }
+
+    // Tries the contact reaction between this particle and one random neighbor,
+    // returning whether anything happened
+    fn react(&mut self, (x, y): (usize, usize), rng: &mut impl Rng) -> bool {
+        let off = (rng.gen_range(-1..=1), rng.gen_range(-1..=1));
+        if off == (0, 0) {
+            return false;
+        }
+        let Some((nx, ny)) = self.offset((x, y), off) else {
+            return false;
+        };
+
+        let reaction = self
+            .particles
+            .get((x, y))
+            .kind()
+            .contact_reaction(self.particles.get((nx, ny)).kind());
+        match reaction {
+            Some((becomes, other_becomes, odds)) if rng.gen_ratio(1, odds) => {
+                for (pos, kind) in [((x, y), becomes), ((nx, ny), other_becomes)] {
+                    if self.particles.get((pos.0, pos.1)).kind() == kind {
+                        continue;
+                    }
+                    self.replace(
+                        pos,
+                        Particle::new(kind).with_turn(self.particles.get((pos.0, pos.1)).turn()),
+                    );
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Sand or mud directly underneath, with water next to it
+    fn on_wet_ground(&self, pos: (usize, usize)) -> bool {
+        let Some(below) = self.offset(pos, (0, 1)) else {
+            return false;
+        };
+        match self.particles.get(below).kind() {
+            Kind::Mud => true,
+            Kind::Sand => self
+                .find_neighbor(below, |p| p.kind() == Kind::Water)
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    fn count_neighbors(&self, (x, y): (usize, usize), pred: impl Fn(&Particle) -> bool) -> usize {
+        let mut count = 0;
+        for y_off in -1..=1 {
+            for x_off in -1..=1 {
+                if (x_off, y_off) == (0, 0) {
+                    continue;
+                }
+                if let Some(pos) = self.offset((x, y), (x_off, y_off)) {
+                    if pred(&self.particles.get(pos)) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    // The cell `(dx, dy)` away from `pos`, through whatever boundary the world
+    // has. Every step from one cell to another goes through here.
+    fn offset(&self, pos: (usize, usize), offset: (i32, i32)) -> Option<(usize, usize)> {
+        self.boundary
+            .offset((self.width(), self.height()), pos, offset)
+    }
+
+    // Without walls, a loose particle that tries to move somewhere `offset`
+    // found no cell for drops out of the world. Returns whether it did.
+    fn fall_off(&mut self, pos: (usize, usize)) -> bool {
+        let kind = self.particles.get(pos).kind();
+        if self.boundary != Boundary::Void
+            || !(kind.is_powder() || kind.is_liquid() || kind.is_gas())
+        {
+            return false;
+        }
+        // Where a strip was cut from the rest of the world isn't an edge
+        if let Some(strip) = &self.strip {
+            if !strip.nearer_an_edge(pos, (self.width(), self.height())) {
+                return false;
+            }
+        }
+        if self.watch == Some(pos) {
+            self.trace
+                .push("fell off the edge of the world".to_string());
+        }
+        self.replace(pos, Particle::default());
+        true
+    }
+
+    // Steps from `pos` up to `cells` times by `(dx, dy)`, stopping short of the
+    // first cell that isn't empty. Returns the last cell reached and how many
+    // steps it took to get there.
+    fn sweep(
+        &self,
+        pos: (usize, usize),
+        (dx, dy): (i32, i32),
+        cells: i32,
+    ) -> ((usize, usize), i32) {
+        let mut reached = (pos, 0);
+        for n in 1..=cells {
+            match self.offset(pos, (dx * n, dy * n)) {
+                Some(next) if self.particles.get(next).empty() => reached = (next, n),
+                _ => break,
+            }
+        }
+        reached
+    }
+
+    fn find_neighbor(
+        &self,
+        (x, y): (usize, usize),
+        pred: impl Fn(&Particle) -> bool,
+    ) -> Option<(usize, usize)> {
+        for y_off in -1..=1 {
+            for x_off in -1..=1 {
+                if (x_off, y_off) == (0, 0) {
+                    continue;
+                }
+                if let Some(pos) = self.offset((x, y), (x_off, y_off)) {
+                    if pred(&self.particles.get(pos)) {
+                        return Some(pos);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Redraws the whole grid, discarding any changes waiting to be drawn.
+    /// `frame` holds a row after row of RGBA pixels, one for each cell.
+    pub fn draw(&mut self, frame: &mut [u8]) {
+        let timing = profile::start();
+        self.changes.clear();
+        self.field_changed = false;
+        for (y, row) in frame.chunks_exact_mut(self.width() * 4).enumerate() {
+            for word in 0..self.occupancy.words_per_row() {
+                let span = self.occupancy.span(word);
+                let occupied = self.occupancy.word(y, word) != 0;
+                for (x, pixel) in span
+                    .clone()
+                    .zip(row[span.start * 4..span.end * 4].chunks_exact_mut(4))
+                {
+                    let particle = if occupied {
+                        self.particles.get((x, y))
+                    } else {
+                        Particle::default()
+                    };
+                    pixel.copy_from_slice(&self.cell_color((x, y), &particle));
+                }
+            }
+        }
+        self.profile.record("draw", timing);
+    }
+
+    /// Redraws the cells in the rectangle between two corners, for clearing away
+    /// whatever was drawn over them. Anything past the edges is left out.
+    pub fn draw_region(
+        &self,
+        frame: &mut [u8],
+        (x0, y0): (usize, usize),
+        (x1, y1): (usize, usize),
+    ) {
+        let (width, height) = (self.width(), self.height());
+        for y in y0.min(y1)..=y0.max(y1).min(height - 1) {
+            for x in x0.min(x1)..=x0.max(x1).min(width - 1) {
+                let i = (y * width + x) * 4;
+                frame[i..i + 4]
+                    .copy_from_slice(&self.cell_color((x, y), &self.particles.get((x, y))));
+            }
+        }
+    }
+
+    /// Whether the next `draw_changes` will redraw every cell rather than just
+    /// the ones that changed, because something all of them are drawn with did
+    pub fn needs_full_redraw(&self) -> bool {
+        self.field_changed
+    }
+
+    /// Redraws only the cells that changed since the last draw, which must have
+    /// been into the same frame
+    pub fn draw_changes(&mut self, frame: &mut [u8]) {
+        if self.field_changed {
+            self.draw(frame);
+            return;
+        }
+        let timing = profile::start();
+        for change in std::mem::take(&mut self.changes) {
+            let (old, new) = (change.old.color(), change.new.color());
+            if old != new {
+                let (x, y) = change.pos;
+                let i = (y * self.width() + x) * 4;
+                frame[i..i + 4].copy_from_slice(&self.cell_color((x, y), &change.new));
+            }
+        }
+        self.profile.record("draw", timing);
+    }
+
+    fn cell_color(&self, pos: (usize, usize), particle: &Particle) -> [u8; 4] {
+        if !particle.empty() {
+            particle.color()
+        } else if self.gravity.inverted(pos) {
+            ANTIGRAVITY_AURA
+        } else {
+            [0x00, 0x00, 0x00, 0xFF]
+        }
+    }
+
+    /// Highlights the structure that `pos` is part of in a frame the world
+    /// has been drawn into
+    pub fn draw_structure(&self, frame: &mut [u8], pos: (usize, usize)) {
+        for (x, y) in self.components.component_cells(pos) {
+            let i = (y * self.width() + x) * 4;
+            let pixel = &mut frame[i..i + 4];
+            for channel in &mut pixel[..3] {
+                *channel = (*channel / 2).saturating_add(0x7F);
+            }
+        }
+    }
+
+    // Every change that can empty or fill a cell, or add or remove a solid
+    // cell, should go through here so that occupancy and structure tracking
+    // stay in sync
+    fn replace(&mut self, (x, y): (usize, usize), particle: Particle) {
+        let old = self.particles.get((x, y));
+        let was_solid = old.kind().is_solid();
+        self.particles.set((x, y), particle);
+        self.record_change((x, y), old, particle);
+        if old.kind() != particle.kind() {
+            if old.kind() == Kind::AntiGravity {
+                self.gravity.remove_emitter((x, y));
+                self.field_changed = true;
+                self.chunks.wake_all();
+            } else if particle.kind() == Kind::AntiGravity {
+                self.gravity.add_emitter((x, y));
+                self.field_changed = true;
+                self.chunks.wake_all();
+            }
+        }
+        self.occupancy.set((x, y), !particle.empty());
+        match (was_solid, particle.kind().is_solid()) {
+            (false, true) => self.components.insert((x, y)),
+            (true, false) => self.components.remove((x, y)),
+            _ => {}
+        }
+    }
+
+    // Changes a particle in place. Use `replace` instead if the cell might
+    // become empty or change between solid and not.
+    fn modify<T>(&mut self, pos: (usize, usize), f: impl FnOnce(&mut Particle) -> T) -> T {
+        let old = self.particles.get(pos);
+        let result = self.particles.modify(pos, f);
+        self.record_change(pos, old, self.particles.get(pos));
+        result
+    }
+
+    fn record_change(&mut self, pos: (usize, usize), old: Particle, new: Particle) {
+        // Short-lived kinds count their age in their counter, which doesn't
+        // change how they look, except that flashes fade
+        let aging = new.kind().lifetime().is_some() && new.kind() != Kind::Flash;
+        if old.kind() != new.kind()
+            || (old.counter != new.counter && !aging)
+            || old.stored() != new.stored()
+            || old.burning() != new.burning()
+            || old.wetness() != new.wetness()
+        {
+            self.changes.push(Change { pos, old, new });
+            self.chunks.touch(pos);
+        }
+    }
+
+    fn swap(&mut self, (x1, y1): (usize, usize), (x2, y2): (usize, usize)) {
+        let first = self.particles.get((x1, y1));
+        self.replace((x1, y1), self.particles.get((x2, y2)));
+        self.replace((x2, y2), first);
+    }
+
+    // Moves a particle, leaving an empty cell behind
+    fn move_particle(&mut self, from: (usize, usize), (to_x, to_y): (usize, usize)) {
+        if from != (to_x, to_y) {
+            self.replace((to_x, to_y), self.particles.get((from.0, from.1)));
+            self.replace(from, Particle::default());
+        }
+    }
+
+    /// Paints a particle of `kind` into a cell, or erases it with `Kind::Empty`
+    pub fn set_pixel(&mut self, (x, y): (usize, usize), kind: Kind) {
+        // Painting past the side of a wrapped world comes round the other side
+        let Some((x, y)) = self.offset((0, 0), (x as i32, y as i32)) else {
+            return;
+        };
+
+        // Sparks are painted onto conductors, which they take the place of until they fade
+        let existing = self.particles.get((x, y));
+        if kind == Kind::Spark && (existing.empty() || existing.kind().conducts()) {
+            self.replace(
+                (x, y),
+                Particle::new(kind)
+                    .with_turn(self.turn())
+                    .with_counter(SPARK_LIFE)
+                    .with_stored(existing.kind())
+                    .with_temperature(existing.temperature),
+            );
+        } else if kind == Kind::Empty && existing.kind() == Kind::Sponge {
+            // Erasing a sponge squeezes out whatever it was holding
+            self.wring_sponge((x, y));
+            self.replace((x, y), Particle::default());
+        } else if kind == Kind::Grate && existing.empty() {
+            self.replace(
+                (x, y),
+                Particle::new(kind)
+                    .with_turn(self.turn())
+                    .with_counter(self.grate_pore_size),
+            );
+        } else if kind == Kind::Empty || existing.empty() {
+            self.replace(
+                (x, y),
+                Particle::new(kind)
+                    .with_turn(self.turn())
+                    .with_temperature(kind.painted_temperature()),
+            );
+        }
+    }
+}
+
+// Pins down the parts of the simulation that the frontend, the console and
+// scripts drive, so that changing them is a deliberate decision
+#[cfg(test)]
+mod api {
+    use super::*;
+
+    type Region = ((usize, usize), (usize, usize));
+
+    #[test]
+    fn signatures() {
+        let _: fn() -> World = World::new;
+        let _: fn(&mut World) = World::update;
+        let _: fn(&World) -> usize = World::width;
+        let _: fn(&World) -> usize = World::height;
+        let _: fn(&World, (usize, usize)) -> Particle = World::particle;
+        let _: fn(&mut World, (usize, usize), Kind) = World::set_pixel;
+        let _: fn(&mut World, Kind, Kind, Option<Region>) = World::replace_kind;
+        let _: fn(&mut World, (usize, usize), Kind) = World::fill_to_level;
+        let _: fn(&mut World, Kind, std::ops::Range<usize>) = World::pour;
+        let _: fn(&mut World) = World::spawn_meteor;
+        let _: fn(&mut World) = World::start_earthquake;
+        let _: fn(&mut World, &mut [u8]) = World::draw;
+        let _: fn(&mut World, &mut [u8]) = World::draw_changes;
+        let _: fn(&Kind) -> [u8; 4] = Kind::color;
+        let _: fn(&Kind) -> &'static str = Kind::name;
+    }
+
+    // Every `pub` declaration in the library's public modules, one to a
+    // line, checked against `public-api.txt` much as cargo-public-api
+    // would. After a deliberate change, run the tests with
+    // `UPDATE_PUBLIC_API=1` to write out the new listing.
+    #[test]
+    fn public_api_matches_the_snapshot() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut listing = String::new();
+        for file in ["lib.rs", "debugger.rs", "profile.rs", "save.rs", "scene.rs"] {
+            let source = std::fs::read_to_string(root.join("src").join(file)).unwrap();
+            // Comments aren't part of the API
+            let mut lines = source
+                .lines()
+                .map(|line| line.split("//").next().unwrap_or_default().trim());
+            while let Some(line) = lines.next() {
+                if !line.starts_with("pub ") {
+                    continue;
+                }
+                // Signatures split over several lines are joined back up
+                let mut item = line.to_string();
+                let depth = |item: &str| {
+                    item.matches(['(', '[']).count() as i32
+                        - item.matches([')', ']']).count() as i32
+                };
+                while depth(&item) > 0 || !item.ends_with(['{', ';', ',']) {
+                    let Some(next) = lines.next() else {
+                        break;
+                    };
+                    if !item.ends_with('(') && !next.starts_with(')') {
+                        item.push(' ');
+                    }
+                    item.push_str(next);
+                }
+                let item = item.replace(",)", ")");
+                let item = item.trim_end_matches(['{', ';', ',']).trim_end();
+                listing.push_str(&format!("{}: {}\n", file, item));
+            }
+        }
+
+        let snapshot = root.join("public-api.txt");
+        if std::env::var_os("UPDATE_PUBLIC_API").is_some() {
+            std::fs::write(&snapshot, &listing).unwrap();
+        }
+        let expected = std::fs::read_to_string(&snapshot).unwrap_or_default();
+        let added: Vec<&str> = listing
+            .lines()
+            .filter(|line| !expected.lines().any(|old| old == *line))
+            .collect();
+        let removed: Vec<&str> = expected
+            .lines()
+            .filter(|line| !listing.lines().any(|new| new == *line))
+            .collect();
+        assert!(
+            added.is_empty() && removed.is_empty(),
+            "the public API changed (rerun with UPDATE_PUBLIC_API=1 if that's \
+             deliberate)\nadded:\n{}\nremoved:\n{}",
+            added.join("\n"),
+            removed.join("\n")
+        );
+    }
+
+    #[test]
+    fn kinds_are_listed_in_declaration_order() {
+        for (i, kind) in Kind::ALL.into_iter().enumerate() {
+            assert_eq!(kind as usize, i);
+        }
+        assert_eq!(Kind::ALL[0], Kind::Empty);
+    }
+
+    #[test]
+    fn kind_names_parse_back() {
+        for kind in Kind::ALL {
+            assert_eq!(kind.name().parse::<Kind>(), Ok(kind));
+            assert_eq!(kind.name().to_lowercase().parse::<Kind>(), Ok(kind));
+        }
+        assert!("Unobtainium".parse::<Kind>().is_err());
+    }
+
+    #[test]
+    fn painting_only_fills_empty_cells() {
+        let mut world = World::new();
+        world.set_pixel((10, 10), Kind::Stone);
+        world.set_pixel((10, 10), Kind::Sand);
+        assert_eq!(world.particle((10, 10)).kind(), Kind::Stone);
+
+        world.set_pixel((10, 10), Kind::Empty);
+        assert!(world.particle((10, 10)).empty());
+
+        // Out of bounds painting is ignored rather than panicking
+        world.set_pixel((GRID_WIDTH as usize, GRID_HEIGHT as usize), Kind::Sand);
+    }
+
+    #[test]
+    fn replacing_within_a_region() {
+        let mut world = World::new();
+        for x in 0..10 {
+            world.set_pixel((x, 0), Kind::Stone);
+        }
+        world.replace_kind(Kind::Stone, Kind::Metal, Some(((0, 0), (4, 0))));
+        let kinds: Vec<Kind> = (0..10).map(|x| world.particle((x, 0)).kind()).collect();
+        assert_eq!(kinds[..5], [Kind::Metal; 5]);
+        assert_eq!(kinds[5..], [Kind::Stone; 5]);
+
+        world.replace_kind(Kind::Stone, Kind::Empty, None);
+        assert!((5..10).all(|x| world.particle((x, 0)).empty()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::count_kind;
+
+    #[test]
+    fn updating_conserves_falling_sand() {
+        let mut world = World::new();
+        for x in 100..110 {
+            world.set_pixel((x, 0), Kind::Sand);
+        }
+        for _ in 0..GRID_HEIGHT * 2 {
+            world.update();
+        }
+        assert_eq!(count_kind(&world, Kind::Sand), 10);
+        assert!(
+            (100..110).any(|x| world.particle((x, GRID_HEIGHT as usize - 1)).kind() == Kind::Sand)
+        );
+    }
+
+    #[test]
+    fn the_void_only_takes_what_moves_off_the_grid() {
+        let mut world = World::new();
+        world.boundary = Boundary::Void;
+        world.pour(Kind::Sand, 100..200);
+        let poured = count_kind(&world, Kind::Sand);
+        assert!(poured > 0);
+
+        // Sand poured in along the top edge falls in rather than out
+        world.update();
+        assert_eq!(count_kind(&world, Kind::Sand), poured);
+
+        // and drops out through the bottom
+        for _ in 0..GRID_HEIGHT * 2 {
+            world.update();
+        }
+        assert_eq!(count_kind(&world, Kind::Sand), 0);
+    }
+
+    #[test]
+    fn worlds_can_be_any_size() {
+        // Neither a whole number of occupancy words nor of chunks across
+        let (width, height) = (100, 70);
+        let mut world = World::with_size((width, height), 7);
+        world.sleeping = true;
+        world.edge_temperatures[Edge::Right as usize] = Some(AMBIENT_TEMPERATURE + 500);
+        for x in 80..width {
+            world.set_pixel((x, 0), Kind::Sand);
+            world.set_pixel((x, 1), Kind::Gravel);
+        }
+        for _ in 0..height * 3 {
+            world.update();
+        }
+        assert_eq!(count_kind(&world, Kind::Sand), width - 80);
+        assert_eq!(count_kind(&world, Kind::Gravel), width - 80);
+        assert!((80..width).all(|x| !world.particle((x, height - 1)).empty()));
+    }
+
+    #[test]
+    fn piles_grow_evenly_on_both_sides() {
+        let mut world = World::new();
+        let (source, height) = (GRID_WIDTH as usize / 2, GRID_HEIGHT as usize - 80);
+        for _ in 0..200 {
+            for x in source - 1..=source + 1 {
+                world.set_pixel((x, height), Kind::Sand);
+            }
+            world.update();
+        }
+        // Each side's sand, weighted by how far out it is, should come to
+        // about the same. Always going the same way across rows piles it up
+        // several times further off to one side than this allows.
+        let (mut sand, mut moment) = (0, 0);
+        for y in 0..GRID_HEIGHT as usize {
+            for x in 0..GRID_WIDTH as usize {
+                if world.particle((x, y)).kind() == Kind::Sand {
+                    sand += 1;
+                    moment += x as i64 - source as i64;
+                }
+            }
+        }
+        assert!(sand > 500);
+        assert!(
+            moment.abs() * 2 < sand,
+            "pile leans by {} over {} grains",
+            moment,
+            sand
+        );
+    }
+
+    #[test]
+    fn poured_water_spreads_evenly_on_both_sides() {
+        let mut world = World::with_seed(1);
+        let source = GRID_WIDTH as usize / 2;
+        for _ in 0..300 {
+            for x in source - 1..=source + 1 {
+                world.set_pixel((x, 20), Kind::Water);
+            }
+            world.update();
+        }
+        // Leveling always moving the first of several equally high cells it
+        // found sends the water off to one side by several cells on average.
+        // Left to chance it comes out within a cell or two.
+        let (mut water, mut moment) = (0, 0);
+        for y in 0..GRID_HEIGHT as usize {
+            for x in 0..GRID_WIDTH as usize {
+                if world.particle((x, y)).kind() == Kind::Water {
+                    water += 1;
+                    moment += x as i64 - source as i64;
+                }
+            }
+        }
+        assert!(water > 500);
+        assert!(
+            moment.abs() < water * 3,
+            "water leans by {} over {} cells",
+            moment,
+            water
+        );
+    }
+
+    #[test]
+    fn earthquakes_leave_what_holds_itself_up_standing() {
+        let mut world = World::with_seed(1);
+        let y = 100;
+        for x in 100..130 {
+            world.set_pixel((x, y), Kind::Metal);
+        }
+        world.start_earthquake();
+        for _ in 0..QUAKE_TICKS {
+            world.update();
+        }
+        assert!((100..130).all(|x| world.particle((x, y)).kind() == Kind::Metal));
+    }
+}
@@ -1,231 +1,231 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
 use log::error;
 use pixels::{Error, Pixels, SurfaceTexture};
 use rand::Rng;
 use winit::dpi::LogicalSize;
-use winit::event::{Event, VirtualKeyCode};
+use winit::event::{Event, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 use winit_input_helper::WinitInputHelper;
 
-const GRID_WIDTH: u32 = 320;
-const GRID_HEIGHT: u32 = 240;
+use basic_pixels::{save, scene, Kind, Pull, World, GRATE_PORE_SIZES, WIND_GUST};
+use camera::Camera;
+use console::{Command, Console};
+use crash::InputLog;
+use flow::FlowView;
+use history::History;
 
-const TOOLBAR_HEIGHT: u32 = 30;
+mod camera;
+mod console;
+mod crash;
+mod flow;
+mod font;
+mod heat;
+mod history;
 
-const WIN_WIDTH: u32 = GRID_WIDTH;
-const WIN_HEIGHT: u32 = GRID_HEIGHT + TOOLBAR_HEIGHT;
+const TOOLBAR_SLOT_WIDTH: u32 = 20;
+const TOOLBAR_ROW_HEIGHT: u32 = 20;
+const TOOLBAR_COLUMNS: u32 = WIN_WIDTH / TOOLBAR_SLOT_WIDTH;
+const TOOLBAR_HEIGHT: u32 =
+    TOOLBAR_ROW_HEIGHT * (TOOLBAR_KINDS.len() as u32).div_ceil(TOOLBAR_COLUMNS);
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Kind {
-    Empty,
-    Sand,
-    Gravel,
-    Water,
-    Stone,
-}
+// How much the span being poured from the top edge grows or shrinks per keypress
+const POUR_WIDTH_STEP: usize = 16;
 
-impl Kind {
-    pub fn color(&self) -> [u8; 4] {
-        match *self {
-            Self::Empty => [0, 0, 0, 0],
-            Self::Sand => [0xC2, 0xB2, 0x80, 0xFF],
-            Self::Gravel => [0x60, 0x60, 0x60, 0xFF],
-            Self::Water => [0x00, 0x96, 0xFF, 0xFF],
-            Self::Stone => [0xCC, 0xCC, 0xCC, 0xFF],
-        }
-    }
-}
+// The simulation runs at TICK_RATE ticks per second (changed with the
+// `tickrate` command) however often frames and input events come in, sped up
+// or slowed down by one of SPEEDS. After a stall it runs at most
+// MAX_CATCH_UP_TICKS times the speed at once, rather than locking up while it
+// works through the whole backlog.
+const TICK_RATE: u32 = 60;
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const NORMAL_SPEED: usize = 2;
+const MAX_CATCH_UP_TICKS: f64 = 4.0;
 
-#[derive(Clone, Copy, Debug)]
-struct Particle {
-    kind: Kind,
-    touched: bool, // To prevent updating the same logical particle multiple times per update
-}
+// How far Shift+WASD moves the camera per frame, in window pixels
+const PAN_STEP: f32 = 4.0;
 
-impl Particle {
-    pub fn empty(&self) -> bool {
-        self.kind == Kind::Empty
-    }
-}
+// The part of the window below the toolbar that the camera shows the grid in
+const VIEW_WIDTH: u32 = 320;
+const VIEW_HEIGHT: u32 = 240;
 
-impl Default for Particle {
-    fn default() -> Particle {
-        Particle {
-            kind: Kind::Empty,
-            touched: false,
-        }
-    }
-}
+// How many cells wide and tall new worlds are. They're bigger than the view,
+// so there's somewhere to pan to.
+const WORLD_SIZE: (usize, usize) = (640, 480);
 
-struct World {
-    particles: [[Particle; 320]; 240],
-    clock: bool,
-}
+const WIN_WIDTH: u32 = VIEW_WIDTH;
+const WIN_HEIGHT: u32 = VIEW_HEIGHT + TOOLBAR_HEIGHT;
 
-impl World {
-    fn new() -> Self {
-        Self {
-            particles: [[Particle::default(); 320]; 240],
-            clock: false,
-        }
-    }
+const NUM_KEYS: [VirtualKeyCode; 10] = {
+    use VirtualKeyCode::*;
+    [Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0]
+};
+// The key that picks up each tool
+const TOOL_KEYS: [(VirtualKeyCode, Tool); 6] = {
+    use VirtualKeyCode::*;
+    [
+        (B, Tool::Brush),
+        (L, Tool::Fill),
+        (R, Tool::Replace),
+        (T, Tool::Measure),
+        (W, Tool::Watch),
+        (N, Tool::Wind),
+    ]
+};
+const TOOLBAR_KINDS: [Kind; 44] = {
+    use Kind::*;
+    [
+        Sand,
+        Gravel,
+        Water,
+        Stone,
+        Salt,
+        Fire,
+        Snow,
+        Metal,
+        Spark,
+        Gunpowder,
+        Clone,
+        Void,
+        Fuse,
+        Wax,
+        Lava,
+        Virus,
+        Seed,
+        Mercury,
+        Slime,
+        Cement,
+        Coal,
+        Gas,
+        AntiGravity,
+        Nitro,
+        Wick,
+        Glue,
+        Thermite,
+        DryIce,
+        Grate,
+        Battery,
+        WaterSource,
+        Drain,
+        Firework,
+        Helium,
+        Soap,
+        Sponge,
+        Tar,
+        Uranium,
+        Antimatter,
+        Crystal,
+        Ant,
+        Ice,
+        Tnt,
+        Acid,
+    ]
+};
 
-    fn update(&mut self) {
-        self.clock = !self.clock;
-        let mut rng = rand::thread_rng();
-
-        let x_ord_hack: Vec<usize> = if self.clock {
-            (0..GRID_WIDTH as usize).collect()
-        } else {
-            ((0..GRID_WIDTH as usize).rev()).collect()
-        };
-        for y in (0..GRID_HEIGHT as usize).rev() {
-            for &x in &x_ord_hack {
-                if self.particles[y][x].touched == self.clock {
-                    continue;
-                }
-                self.particles[y][x].touched = !self.particles[y][x].touched;
-
-                match self.particles[y][x].kind {
-                    Kind::Empty | Kind::Stone => {}
-                    Kind::Sand => {
-                        if (y as u32) < GRID_HEIGHT - 1 {
-                            if self.particles[y + 1][x].empty()
-                                || self.particles[y + 1][x].kind == Kind::Water
-                            {
-                                let self_kind = self.particles[y][x];
-                                self.particles[y][x] = self.particles[y + 1][x];
-                                self.particles[y + 1][x] = self_kind;
-                            } else {
-                                let new_y = y + 1;
-                                let new_x = x as i32 + (rng.gen::<bool>() as i32 * 2 - 1);
-                                if new_x >= 0 && new_x < GRID_WIDTH as i32 {
-                                    let new_x = new_x as usize;
-                                    if self.particles[new_y][new_x].empty()
-                                        || self.particles[new_y][new_x].kind == Kind::Water
-                                    {
-                                        let self_kind = self.particles[y][x];
-                                        self.particles[y][x] = self.particles[new_y][new_x];
-                                        self.particles[new_y][new_x] = self_kind;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Kind::Gravel => {
-                        if (y as u32) < GRID_HEIGHT - 1 {
-                            if self.particles[y + 1][x].empty()
-                                || self.particles[y + 1][x].kind == Kind::Water
-                            {
-                                let self_kind = self.particles[y][x];
-                                self.particles[y][x] = self.particles[y + 1][x];
-                                self.particles[y + 1][x] = self_kind;
-                            }
-                        }
-                    }
-                    Kind::Water => {
-                        let down_valid = y < GRID_HEIGHT as usize - 1;
-                        if down_valid && self.particles[y + 1][x].empty() {
-                            self.particles[y + 1][x] = self.particles[y][x];
-                            self.particles[y][x] = Particle::default();
-                        } else {
-                            // TODO: Rename and refactor this
-                            let new_y = y + 1;
-                            let (x_off, x_check_off) = {
-                                let n = rng.gen_range(1..3);
-                                let sign = rng.gen::<bool>() as i32 * 2 - 1;
-                                (n * sign, (n - 1) * sign)
-                            };
-                            let new_x1 = x as i32 + x_off;
-                            let check_x1 = x as i32 + x_check_off;
-                            let new_x1_valid = new_x1 >= 0 && new_x1 < GRID_WIDTH as i32;
-
-                            let x_off = rng.gen::<bool>() as i32 * 2 - 1;
-                            let new_x4 = x as i32 - x_off;
-                            let new_x4_valid = new_x4 >= 0 && new_x4 < GRID_WIDTH as i32;
-
-                            let (x_off, x_check_off) = {
-                                let n = rng.gen_range(2..5);
-                                let sign = rng.gen::<bool>() as i32 * 2 - 1;
-                                (n * sign, (n - 1) * sign)
-                            };
-                            let new_x5 = x as i32 + x_off;
-                            let check_x5 = x as i32 + x_check_off;
-                            let new_x5_valid = new_x5 >= 0 && new_x5 < GRID_WIDTH as i32;
-                            if down_valid
-                                && new_x1_valid
-                                && self.particles[new_y][new_x1 as usize].empty()
-                                && self.particles[new_y][check_x1 as usize].kind == Kind::Water
-                            {
-                                self.particles[new_y][new_x1 as usize] = self.particles[y][x];
-                                self.particles[y][x] = Particle::default();
-                            } else if new_x4_valid && self.particles[y][new_x4 as usize].empty() {
-                                self.particles[y][new_x4 as usize] = self.particles[y][x];
-                                self.particles[y][x] = Particle::default();
-                            } else if down_valid
-                                && new_x5_valid
-                                && self.particles[y][new_x5 as usize].empty()
-                                && self.particles[new_y][check_x5 as usize].kind == Kind::Water
-                            {
-                                self.particles[y][new_x5 as usize] = self.particles[y][x];
-                                self.particles[y][x] = Particle::default();
-                            }
-                        }
-                    }
-                }
-            }
-        }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tool {
+    Brush,
+    Fill,
+    Replace,
+    Measure,
+    Watch,
+    Wind,
+}
+
+/// What the measure tool reports for a drag between two cells,
+/// or for a click on a single empty cell
+fn measure(world: &World, start: (usize, usize), end: (usize, usize)) -> String {
+    if start == end {
+        return format!("VOLUME {}", world.basin(start).len());
     }
 
-    fn draw(&self, frame: &mut [u8]) {
-        for (i, pixel) in frame
-            .chunks_exact_mut(4)
-            .skip((WIN_WIDTH * (WIN_HEIGHT - GRID_HEIGHT)) as usize)
-            .enumerate()
-        {
-            let x = i % GRID_WIDTH as usize;
-            let y = i / GRID_WIDTH as usize;
+    let (dx, dy) = (start.0.abs_diff(end.0), start.1.abs_diff(end.1));
+    let distance = ((dx * dx + dy * dy) as f64).sqrt();
+    format!(
+        "DIST {:.1}  AREA {}X{}={}",
+        distance,
+        dx + 1,
+        dy + 1,
+        (dx + 1) * (dy + 1)
+    )
+}
 
-            let particle = &self.particles[y][x];
+// Summarizes the differences between two worlds found by `World::differences`
+fn describe_differences(first: &World, second: &World, cells: &[(usize, usize)]) -> String {
+    const LISTED: usize = 10;
 
-            let rgba = if particle.kind != Kind::Empty {
-                particle.kind.color()
-            } else {
-                [0x00, 0x00, 0x00, 0xFF]
-            };
+    let (Some(&(x0, _)), Some(&(x1, _))) = (
+        cells.iter().min_by_key(|(x, _)| x),
+        cells.iter().max_by_key(|(x, _)| x),
+    ) else {
+        return format!("identical at ticks {} and {}", first.tick(), second.tick());
+    };
+    let (y0, y1) = (cells[0].1, cells[cells.len() - 1].1);
+    let mut description = format!(
+        "{} cells differ at ticks {} and {}, between {:?} and {:?}",
+        cells.len(),
+        first.tick(),
+        second.tick(),
+        (x0, y0),
+        (x1, y1)
+    );
+    for &pos in cells.iter().take(LISTED) {
+        description.push_str(&format!(
+            "\n  {:?}: {:?} vs {:?}",
+            pos,
+            first.particle(pos),
+            second.particle(pos)
+        ));
+    }
+    if cells.len() > LISTED {
+        description.push_str(&format!("\n  and {} more", cells.len() - LISTED));
+    }
+    description
+}
 
-            pixel.copy_from_slice(&rgba);
-        }
+fn draw_differences(canvas: &mut [u8], width: usize, cells: &[(usize, usize)]) {
+    for &(x, y) in cells {
+        let i = (y * width + x) * 4;
+        canvas[i..i + 4].copy_from_slice(&[0xFF, 0x20, 0x40, 0xFF]);
     }
+}
 
-    fn set_pixel(&mut self, (x, y): (usize, usize), kind: Kind) {
-        if x < GRID_WIDTH as usize
-            && y < GRID_HEIGHT as usize
-            && (kind == Kind::Empty || self.particles[y][x].empty())
-        {
-            self.particles[y][x] = Particle {
-                kind,
-                touched: self.clock,
-            };
+fn draw_selection_box(
+    canvas: &mut [u8],
+    width: usize,
+    (x0, y0): (usize, usize),
+    (x1, y1): (usize, usize),
+) {
+    let (left, right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+    for y in top..=bottom {
+        for x in left..=right {
+            if x == left || x == right || y == top || y == bottom {
+                let i = (y * width + x) * 4;
+                canvas[i..i + 4].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+            }
         }
     }
 }
 
-const NUM_KEYS: [VirtualKeyCode; 10] = {
-    use VirtualKeyCode::*;
-    [Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0]
-};
-const TOOLBAR_KINDS: [Kind; 4] = {
-    use Kind::*;
-    [Sand, Gravel, Water, Stone]
-};
-
 struct Toolbar {}
 
 impl Toolbar {
+    /// The kind in the toolbar slot under a point in the toolbar, if any
+    fn kind_at(&self, (x, y): (usize, usize)) -> Option<Kind> {
+        let column = x / TOOLBAR_SLOT_WIDTH as usize;
+        let row = y / TOOLBAR_ROW_HEIGHT as usize;
+        TOOLBAR_KINDS
+            .get(row * TOOLBAR_COLUMNS as usize + column)
+            .copied()
+    }
+
     fn draw(&self, frame: &mut [u8], selected_kind: Kind) {
         for (i, pixel) in frame
             .chunks_exact_mut(4)
@@ -235,18 +235,17 @@ impl Toolbar {
             let x = i % WIN_WIDTH as usize;
             let y = i / WIN_WIDTH as usize;
 
-            let part_size = (WIN_WIDTH / 10) as usize;
             let part_gap = 4;
-            let top_gap = 5;
-            let which_part = x / part_size;
-            let x_in_part = x % part_size;
+            let top_gap = 4;
+            let x_in_part = x % TOOLBAR_SLOT_WIDTH as usize;
+            let y_in_part = y % TOOLBAR_ROW_HEIGHT as usize;
 
-            let do_color = (y > top_gap && y < TOOLBAR_HEIGHT as usize - top_gap)
-                && (x_in_part >= part_gap && x_in_part < part_size - part_gap);
+            let do_color = (y_in_part >= top_gap
+                && y_in_part < TOOLBAR_ROW_HEIGHT as usize - top_gap)
+                && (x_in_part >= part_gap && x_in_part < TOOLBAR_SLOT_WIDTH as usize - part_gap);
 
             let mut rgba = [0x00, 0x00, 0x00, 0xFF];
-            if which_part < TOOLBAR_KINDS.len() {
-                let which_kind = TOOLBAR_KINDS[which_part];
+            if let Some(which_kind) = self.kind_at((x, y)) {
                 if which_kind == selected_kind && !do_color {
                     rgba = [0x7f, 0x00, 0x00, 0xFF];
                 } else if do_color {
@@ -258,8 +257,154 @@ impl Toolbar {
     }
 }
 
+// The part of the window's frame below the toolbar, where the camera's view is drawn
+fn view_frame(frame: &mut [u8]) -> &mut [u8] {
+    &mut frame[(TOOLBAR_HEIGHT * WIN_WIDTH) as usize * 4..]
+}
+
+// A point in the window's pixels as a point in the view, if it's below the
+// toolbar
+fn view_pos((pixel_x, pixel_y): (usize, usize)) -> Option<(usize, usize)> {
+    Some((pixel_x, pixel_y.checked_sub(TOOLBAR_HEIGHT as usize)?))
+}
+
+// An area of the view from one corner to the other, in view pixels
+type ViewArea = ((usize, usize), (usize, usize));
+
+// Draws a line of text at a point in the view, noting down what it covers so
+// it can be cleared away next frame
+fn draw_view_text(
+    frame: &mut [u8],
+    covered: &mut Vec<ViewArea>,
+    (x, y): (usize, usize),
+    text: &str,
+) {
+    font::draw_text(frame, (x, y + TOOLBAR_HEIGHT as usize), text);
+    let (width, height) = font::text_size(text);
+    covered.push(((x, y), (x + width, y + height)));
+}
+
+// The mouse's position in the view, if it's in the window below the toolbar
+fn mouse_view_pos(pixels: &Pixels, input: &WinitInputHelper) -> Option<(usize, usize)> {
+    input
+        .mouse()
+        .and_then(|mouse_pos| pixels.window_pos_to_pixel(mouse_pos).ok())
+        .and_then(view_pos)
+}
+
+/// The grid cell under the mouse, if it's over the grid
+fn mouse_cell(
+    pixels: &Pixels,
+    input: &WinitInputHelper,
+    camera: &Camera,
+) -> Option<(usize, usize)> {
+    mouse_view_pos(pixels, input)
+        .map(|pos| camera.cell_at(pos))
+        .filter(|&pos| camera.on_grid(pos))
+}
+
+// Where the mouse was and is now in the view, if it's in the view now. Where
+// it was is clamped to the view, so a stroke that comes in from over the
+// toolbar or outside the window starts at the view's edge.
+fn mouse_view_move(
+    pixels: &Pixels,
+    input: &WinitInputHelper,
+) -> Option<((usize, usize), (usize, usize))> {
+    let (mx, my) = input.mouse()?;
+    let (dx, dy) = input.mouse_diff();
+    let now = view_pos(pixels.window_pos_to_pixel((mx, my)).ok()?)?;
+    let (prev_x, prev_y) = pixels
+        .window_pos_to_pixel((mx - dx, my - dy))
+        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
+    let prev = (prev_x, prev_y.saturating_sub(TOOLBAR_HEIGHT as usize));
+    Some((prev, now))
+}
+
+// The value following `flag` on the command line, if the flag was given
+fn arg_value(flag: &str) -> Option<Result<String, String>> {
+    let args: Vec<String> = std::env::args().collect();
+    let i = args.iter().position(|arg| arg == flag)?;
+    Some(
+        args.get(i + 1)
+            .cloned()
+            .ok_or_else(|| format!("usage: {} <value>", flag)),
+    )
+}
+
+/// Times updating and drawing a busy scene, for comparing changes to the simulation.
+/// Run with `cargo run --release -- --bench`, adding `--parallel` to update
+/// on several threads.
+fn bench() {
+    const BENCH_TICKS: u32 = 1000;
+
+    let mut world = World::new();
+    world.parallel = std::env::args().any(|arg| arg == "--parallel");
+    let mut rng = rand::thread_rng();
+    for y in world.height() / 3..world.height() {
+        for x in 0..world.width() {
+            let kind = match rng.gen_range(0..8) {
+                0..=2 => Kind::Sand,
+                3..=5 => Kind::Water,
+                6 => Kind::Gravel,
+                _ => Kind::Empty,
+            };
+            world.set_pixel((x, y), kind);
+        }
+    }
+
+    let mut frame = vec![0; world.width() * world.height() * 4];
+    let start = Instant::now();
+    for _ in 0..BENCH_TICKS {
+        world.update();
+        world.clear_changes();
+    }
+    let elapsed = start.elapsed();
+    println!("update: {:.3?} per tick", elapsed / BENCH_TICKS);
+
+    let start = Instant::now();
+    for _ in 0..BENCH_TICKS {
+        world.draw(&mut frame);
+    }
+    let elapsed = start.elapsed();
+    println!("draw: {:.3?} per frame", elapsed / BENCH_TICKS);
+
+    let mut elapsed = std::time::Duration::ZERO;
+    for _ in 0..BENCH_TICKS {
+        world.update();
+        let start = Instant::now();
+        world.draw_changes(&mut frame);
+        elapsed += start.elapsed();
+    }
+    println!("draw changes: {:.3?} per frame", elapsed / BENCH_TICKS);
+}
+
 fn main() -> Result<(), Error> {
+    if std::env::args().any(|arg| arg == "--bench") {
+        bench();
+        return Ok(());
+    }
+
+    // `--diff <path> <path>` compares two saved worlds without opening a window
+    if let Some(first) = arg_value("--diff") {
+        let args: Vec<String> = std::env::args().collect();
+        let second = args.iter().skip_while(|arg| *arg != "--diff").nth(2);
+        match (first, second) {
+            (Ok(first), Some(second)) => {
+                match (save::read(Path::new(&first)), save::read(Path::new(second))) {
+                    (Ok(first), Ok(second)) => {
+                        let cells = first.differences(&second);
+                        println!("{}", describe_differences(&first, &second, &cells));
+                    }
+                    (Err(e), _) | (_, Err(e)) => eprintln!("{}", e),
+                }
+            }
+            _ => eprintln!("usage: --diff <path> <path>"),
+        }
+        return Ok(());
+    }
+
     env_logger::init();
+    crash::install_panic_hook();
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
     let window = {
@@ -277,121 +422,650 @@ fn main() -> Result<(), Error> {
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
         Pixels::new(WIN_WIDTH, WIN_HEIGHT, surface_texture)?
     };
-    let mut world = World::new();
+    let world = if let Some(dir) = arg_value("--dump") {
+        dir.and_then(|dir| crash::load_dump(Path::new(&dir)).map_err(|e| e.to_string()))
+    } else if let Some(seed) = arg_value("--seed") {
+        seed.and_then(|seed| {
+            seed.parse()
+                .map(|seed| scene::generate(WORLD_SIZE, seed))
+                .map_err(|_| format!("invalid seed `{}`", seed))
+        })
+    } else {
+        Ok(World::with_size(
+            WORLD_SIZE,
+            rand::thread_rng().gen::<u32>() as u64,
+        ))
+    };
+    let mut world = match world {
+        Ok(world) => world,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(());
+        }
+    };
     let toolbar = Toolbar {};
+    let console = Console::spawn();
 
     let mut paused = false;
     let mut selected_kind = Kind::Sand;
+    let mut tool = Tool::Brush;
+    let mut selection_start: Option<(usize, usize)> = None;
+    let mut selected_structure: Option<(usize, usize)> = None;
+    let mut measurement: Option<String> = None;
+    let mut history = History::new();
+    let mut show_timeline = false;
+    let mut heat_view = false;
+    let mut pour_width = world.width();
+    let mut camera = Camera::new((world.width(), world.height()));
+    // The whole grid as it's drawn, which the camera shows part of
+    let mut canvas = vec![0; world.width() * world.height() * 4];
+    let mut full_redraw = true;
+    // Parts of the grid that small overlays were drawn over last frame, which
+    // have to be redrawn even if nothing under them changed
+    let mut overlays: Vec<((usize, usize), (usize, usize))> = Vec::new();
+    // Parts of the view that text and the timeline were drawn over last frame,
+    // in view pixels, which have to be copied out of the canvas again
+    let mut covered: Vec<ViewArea> = Vec::new();
+    // The selection the toolbar was last drawn with
+    let mut toolbar_drawn: Option<Kind> = None;
+    let mut flow_view: Option<FlowView> = None;
+    let mut input_log = InputLog::new();
+    // Cells highlighted by the last `diff` command, until Backspace clears them
+    let mut differences: Vec<(usize, usize)> = Vec::new();
+    let mut tick_rate = TICK_RATE;
+    let mut speed = NORMAL_SPEED;
+    let mut last_step = Instant::now();
+    // Time that has passed but hasn't been simulated yet
+    let mut lag = Duration::ZERO;
+
+    // The world as it was before the tick being run, while one is. A panic
+    // partway through a tick leaves the world half updated, so this is what
+    // goes into a crash dump instead, ready to run the tick again.
+    let mut before_tick: Option<World> = None;
 
     event_loop.run(move |event, _, control_flow| {
-        // Draw the current frame
-        if let Event::RedrawRequested(_) = event {
-            world.draw(pixels.get_frame());
-            toolbar.draw(pixels.get_frame(), selected_kind);
-            if pixels
-                .render()
-                .map_err(|e| error!("pixels.render() failed: {}", e))
-                .is_err()
+        let handled = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            if let Event::WindowEvent {
+                event:
+                    window_event @ (WindowEvent::KeyboardInput { .. }
+                    | WindowEvent::MouseInput { .. }
+                    | WindowEvent::CursorMoved { .. }),
+                ..
+            } = &event
             {
-                *control_flow = ControlFlow::Exit;
-                return;
+                input_log.record(world.tick(), window_event);
             }
-        }
 
-        // Handle input events
-        if input.update(&event) {
-            // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
-                *control_flow = ControlFlow::Exit;
-                return;
+            // A world of another size was loaded, so everything sized to the old
+            // one starts over
+            if canvas.len() != world.width() * world.height() * 4 {
+                canvas = vec![0; world.width() * world.height() * 4];
+                camera = Camera::new((world.width(), world.height()));
+                if flow_view.is_some() {
+                    flow_view = Some(FlowView::new((world.width(), world.height())));
+                }
+                pour_width = pour_width.min(world.width());
+                selection_start = None;
+                selected_structure = None;
+                differences.clear();
+                overlays.clear();
+                full_redraw = true;
             }
 
-            if input.key_pressed(VirtualKeyCode::Space) {
-                paused = !paused;
-            } else if input.key_pressed(VirtualKeyCode::F) {
-                paused = true;
-            }
+            // Draw the current frame
+            if let Event::RedrawRequested(_) = event {
+                if let Some(flow_view) = &mut flow_view {
+                    flow_view.record(world.changes());
+                }
+                // Only the cells redrawn into the canvas need copying into the
+                // view, unless all of them were
+                let mut redraw_view = full_redraw || world.needs_full_redraw();
+                let mut regions: Vec<_> = world
+                    .changes()
+                    .iter()
+                    .map(|change| (change.pos, change.pos))
+                    .collect();
+                if full_redraw {
+                    world.draw(&mut canvas);
+                } else {
+                    for (start, end) in overlays.drain(..) {
+                        world.draw_region(&mut canvas, start, end);
+                        regions.push((start, end));
+                    }
+                    world.draw_changes(&mut canvas);
+                }
+                overlays.clear();
+                // Overlays are drawn over the grid, so the next frame has to redraw it all
+                full_redraw = false;
+                if heat_view {
+                    heat::draw(&world, &mut canvas);
+                    full_redraw = true;
+                }
+                if let Some(flow_view) = &flow_view {
+                    flow_view.draw(&mut canvas);
+                    full_redraw = true;
+                }
+                if let Some(pos) = selected_structure {
+                    world.draw_structure(&mut canvas, pos);
+                    full_redraw = true;
+                }
+                if let Some(pos) = world.watch {
+                    draw_selection_box(&mut canvas, world.width(), pos, pos);
+                    overlays.push((pos, pos));
+                }
+                if !differences.is_empty() {
+                    draw_differences(&mut canvas, world.width(), &differences);
+                    full_redraw = true;
+                }
+                if let (Some(start), Some(end)) =
+                    (selection_start, mouse_cell(&pixels, &input, &camera))
+                {
+                    draw_selection_box(&mut canvas, world.width(), start, end);
+                    overlays.push((start, end));
+                }
+                redraw_view |= full_redraw;
+                regions.extend(&overlays);
+                if redraw_view {
+                    camera.draw(&canvas, view_frame(pixels.get_frame()));
+                } else {
+                    camera.draw_regions(&canvas, view_frame(pixels.get_frame()), regions);
+                }
 
-            // Resize the window
-            if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
+                // Everything from here on is drawn over the view, so whatever was
+                // drawn last frame is cleared away first
+                for (start, end) in covered.drain(..) {
+                    camera.draw_area(&canvas, view_frame(pixels.get_frame()), start, end);
+                }
+                if show_timeline {
+                    history.draw(pixels.get_frame());
+                    let top = (VIEW_HEIGHT - history::TIMELINE_HEIGHT) as usize;
+                    covered.push(((0, top), (VIEW_WIDTH as usize, VIEW_HEIGHT as usize)));
+                }
+                if let Some(text) = &measurement {
+                    draw_view_text(pixels.get_frame(), &mut covered, (1, 1), text);
+                }
+                #[cfg(feature = "profile")]
+                for (i, (phase, time)) in world.profile().phases().iter().enumerate() {
+                    let text = format!("{} {:.2}MS", phase, time.as_secs_f64() * 1000.0);
+                    let y = (i + 1) * (font::GLYPH_HEIGHT + 2) + 1;
+                    draw_view_text(pixels.get_frame(), &mut covered, (1, y), &text);
+                }
+                let seed = if speed == NORMAL_SPEED {
+                    format!("seed {}", world.seed())
+                } else {
+                    format!("seed {} x{}", world.seed(), SPEEDS[speed])
+                };
+                let seed_x = WIN_WIDTH as usize - font::text_size(&seed).0 - 1;
+                draw_view_text(pixels.get_frame(), &mut covered, (seed_x, 1), &seed);
+                if toolbar_drawn != Some(selected_kind) {
+                    toolbar.draw(pixels.get_frame(), selected_kind);
+                    toolbar_drawn = Some(selected_kind);
+                }
+                if pixels
+                    .render()
+                    .map_err(|e| error!("pixels.render() failed: {}", e))
+                    .is_err()
+                {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
             }
 
-            let num_key_pressed_index = NUM_KEYS.iter().position(|&key| input.key_pressed(key));
-            if let Some(num_key_pressed_index) = num_key_pressed_index {
-                if num_key_pressed_index < TOOLBAR_KINDS.len() {
-                    selected_kind = TOOLBAR_KINDS[num_key_pressed_index];
+            // Handle input events
+            if input.update(&event) {
+                // Close events
+                if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+
+                if input.key_pressed(VirtualKeyCode::Space) {
+                    paused = !paused;
+                } else if input.key_pressed(VirtualKeyCode::F) {
+                    paused = true;
+                }
+
+                if input.key_pressed(VirtualKeyCode::C) {
+                    if let Some(pos) = mouse_cell(&pixels, &input, &camera) {
+                        world.place_candle(pos);
+                    }
+                }
+
+                // G builds a surprise scene. Its seed is shown in the corner, and
+                // passing it to `--seed` or the `seed` command builds it again.
+                if input.key_pressed(VirtualKeyCode::G) {
+                    world = scene::generate(WORLD_SIZE, rand::thread_rng().gen::<u32>() as u64);
+                    full_redraw = true;
+                    paused = false;
+                }
+
+                if input.key_pressed(VirtualKeyCode::M) {
+                    world.spawn_meteor();
+                }
+
+                if input.key_pressed(VirtualKeyCode::Q) {
+                    world.start_earthquake();
+                }
+
+                // [ and ] change the pore size of newly painted grates
+                if input.key_pressed(VirtualKeyCode::LBracket) {
+                    world.grate_pore_size = world
+                        .grate_pore_size
+                        .saturating_sub(1)
+                        .max(*GRATE_PORE_SIZES.start());
+                } else if input.key_pressed(VirtualKeyCode::RBracket) {
+                    world.grate_pore_size =
+                        (world.grate_pore_size + 1).min(*GRATE_PORE_SIZES.end());
+                }
+
+                if input.key_pressed(VirtualKeyCode::Back) {
+                    differences.clear();
+                }
+
+                if input.key_pressed(VirtualKeyCode::I) {
+                    world.structural_integrity = !world.structural_integrity;
+                }
+
+                if input.key_pressed(VirtualKeyCode::O) {
+                    world.lod = !world.lod;
+                }
+
+                if input.key_pressed(VirtualKeyCode::U) {
+                    world.shuffled_order = !world.shuffled_order;
+                }
+
+                if input.key_pressed(VirtualKeyCode::Z) {
+                    world.sleeping = !world.sleeping;
+                }
+
+                if input.key_pressed(VirtualKeyCode::J) {
+                    world.parallel = !world.parallel;
+                }
+                if let Some(pos) = mouse_cell(&pixels, &input, &camera) {
+                    world.lod_focus = pos;
+                }
+
+                if input.key_pressed(VirtualKeyCode::V) {
+                    flow_view = match flow_view {
+                        Some(_) => None,
+                        None => Some(FlowView::new((world.width(), world.height()))),
+                    };
+                    full_redraw = true;
+                }
+
+                if input.key_pressed(VirtualKeyCode::H) {
+                    show_timeline = !show_timeline;
+                }
+
+                // The arrow keys point gravity, and X pulls everything towards the cursor
+                for (key, pull) in [
+                    (VirtualKeyCode::Up, Pull::Direction(0, -1)),
+                    (VirtualKeyCode::Down, Pull::DOWN),
+                    (VirtualKeyCode::Left, Pull::Direction(-1, 0)),
+                    (VirtualKeyCode::Right, Pull::Direction(1, 0)),
+                ] {
+                    if input.key_pressed(key) {
+                        world.pull = pull;
+                    }
+                }
+                if input.key_pressed(VirtualKeyCode::X) {
+                    if let Some(pos) = mouse_cell(&pixels, &input, &camera) {
+                        world.pull = Pull::Radial(pos);
+                    }
+                }
+
+                if input.key_pressed(VirtualKeyCode::K) {
+                    heat_view = !heat_view;
+                    full_redraw = true;
+                }
+
+                // Clicking or dragging along the timeline jumps back to that checkpoint
+                let over_timeline = show_timeline
+                    && input
+                        .mouse()
+                        .and_then(|mouse_pos| pixels.window_pos_to_pixel(mouse_pos).ok())
+                        .is_some_and(|pos| history.contains(pos));
+                if over_timeline && input.mouse_held(0) {
+                    if let Some(Ok((pixel_x, _))) = input
+                        .mouse()
+                        .map(|mouse_pos| pixels.window_pos_to_pixel(mouse_pos))
+                    {
+                        if let Some(checkpoint) = history.seek(pixel_x) {
+                            world = checkpoint.clone();
+                            full_redraw = true;
+                            paused = true;
+                        }
+                    }
+                }
+
+                // Shift+W pans the camera rather than picking up the watch tool
+                if let Some(&(key, key_tool)) =
+                    TOOL_KEYS.iter().find(|&&(key, _)| input.key_pressed(key))
+                {
+                    if key != VirtualKeyCode::W || !input.held_shift() {
+                        tool = key_tool;
+                    }
+                }
+                if tool != Tool::Measure {
+                    measurement = None;
+                }
+
+                while let Some(command) = console.poll() {
+                    match command {
+                        Ok(Command::Replace { from, to }) => world.replace_kind(from, to, None),
+                        Ok(Command::Seed { seed }) => {
+                            world = scene::generate(WORLD_SIZE, seed);
+                            full_redraw = true;
+                            paused = false;
+                        }
+                        Ok(Command::Edge { edge, temperature }) => {
+                            world.edge_temperatures[edge as usize] = temperature;
+                        }
+                        Ok(Command::Boundary { boundary }) => world.boundary = boundary,
+                        Ok(Command::TickRate { rate }) => tick_rate = rate,
+                        Ok(Command::Save { path }) => {
+                            if let Err(e) = save::write(&world, Path::new(&path)) {
+                                eprintln!("couldn't save to {}: {}", path, e);
+                            }
+                        }
+                        Ok(Command::Diff { first, second }) => {
+                            let first = save::read(Path::new(&first));
+                            let second = second.map(|second| save::read(Path::new(&second)));
+                            match (first, second) {
+                                (Ok(first), None) => {
+                                    differences = first.differences(&world);
+                                    println!(
+                                        "{}",
+                                        describe_differences(&first, &world, &differences)
+                                    );
+                                }
+                                (Ok(first), Some(Ok(second))) => {
+                                    differences = first.differences(&second);
+                                    println!(
+                                        "{}",
+                                        describe_differences(&first, &second, &differences)
+                                    );
+                                }
+                                (Err(e), _) | (_, Some(Err(e))) => eprintln!("{}", e),
+                            }
+                            full_redraw = true;
+                        }
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+
+                // Resize the window
+                if let Some(size) = input.window_resized() {
+                    pixels.resize_surface(size.width, size.height);
                 }
-            }
 
-            let left_click = input.mouse_held(0);
-            let right_click = input.mouse_held(1);
+                let num_key_pressed_index = NUM_KEYS.iter().position(|&key| input.key_pressed(key));
+                if let Some(num_key_pressed_index) = num_key_pressed_index {
+                    if num_key_pressed_index < TOOLBAR_KINDS.len() {
+                        selected_kind = TOOLBAR_KINDS[num_key_pressed_index];
+                    }
+                }
+
+                // Middle click highlights the whole structure under the cursor, and
+                // dragging with it pans the camera
+                if input.mouse_pressed(2) {
+                    selected_structure =
+                        mouse_cell(&pixels, &input, &camera).filter(|&pos| world.in_structure(pos));
+                }
+                if input.mouse_held(2) {
+                    if let Some((prev, now)) = mouse_view_move(&pixels, &input) {
+                        camera.pan((prev.0 as f32 - now.0 as f32, prev.1 as f32 - now.1 as f32));
+                    }
+                }
 
-            if left_click || right_click {
-                if input.mouse_pressed(0) {
+                // Shift+WASD pans too, and scrolling zooms in and out around the cursor
+                if input.held_shift() {
+                    let keys = [
+                        (VirtualKeyCode::W, (0.0, -PAN_STEP)),
+                        (VirtualKeyCode::A, (-PAN_STEP, 0.0)),
+                        (VirtualKeyCode::S, (0.0, PAN_STEP)),
+                        (VirtualKeyCode::D, (PAN_STEP, 0.0)),
+                    ];
+                    for (key, step) in keys {
+                        if input.key_held(key) {
+                            camera.pan(step);
+                        }
+                    }
+                }
+                let scroll = input.scroll_diff();
+                if scroll != 0.0 {
+                    let center = (VIEW_WIDTH as usize / 2, VIEW_HEIGHT as usize / 2);
+                    camera.zoom_at(
+                        mouse_view_pos(&pixels, &input).unwrap_or(center),
+                        scroll > 0.0,
+                    );
+                }
+
+                if input.mouse_pressed(0) && !over_timeline {
                     if let Some(Ok((pixel_x, pixel_y))) = input
                         .mouse()
                         .map(|mouse_pos| pixels.window_pos_to_pixel(mouse_pos))
                     {
                         if pixel_y < TOOLBAR_HEIGHT as usize {
-                            let which_part = pixel_x / (WIN_WIDTH as usize / 10);
-                            if which_part < TOOLBAR_KINDS.len() {
-                                selected_kind = TOOLBAR_KINDS[which_part];
+                            if let Some(kind) = toolbar.kind_at((pixel_x, pixel_y)) {
+                                selected_kind = kind;
                             }
                         }
                     }
+
+                    match tool {
+                        Tool::Fill => {
+                            if let Some(pos) = mouse_cell(&pixels, &input, &camera) {
+                                world.fill_to_level(pos, selected_kind);
+                            }
+                        }
+                        Tool::Replace | Tool::Measure => {
+                            selection_start = mouse_cell(&pixels, &input, &camera)
+                        }
+                        // Step with F to see what the clicked cell does next
+                        Tool::Watch => {
+                            world.watch = mouse_cell(&pixels, &input, &camera);
+                            world.trace.clear();
+                        }
+                        Tool::Brush | Tool::Wind => {}
+                    }
                 }
 
-                let click_kind = if left_click {
-                    selected_kind
-                } else {
-                    Kind::Empty
-                };
+                if let (Some(start), Some(end)) =
+                    (selection_start, mouse_cell(&pixels, &input, &camera))
+                {
+                    if tool == Tool::Measure {
+                        measurement = Some(measure(&world, start, end));
+                    } else if input.mouse_released(0) {
+                        // Dragging with the replace tool turns everything in the box that matches
+                        // the kind where the drag started into the selected kind. A click without
+                        // a drag replaces it everywhere.
+                        let from = world.particle((start.0, start.1)).kind();
+                        let region = if start == end {
+                            None
+                        } else {
+                            Some((start, end))
+                        };
+                        world.replace_kind(from, selected_kind, region);
+                    }
+                }
+                if input.mouse_released(0) {
+                    selection_start = None;
+                }
 
-                let (mouse_cell, mouse_prev_cell) = input
-                    .mouse()
-                    .map(|(mx, my)| {
-                        let (dx, dy) = input.mouse_diff();
-                        let prev_x = mx - dx;
-                        let prev_y = my - dy;
-
-                        let (mx_i, my_i) = pixels
-                            .window_pos_to_pixel((mx, my))
-                            .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                        let (px_i, py_i) = pixels
-                            .window_pos_to_pixel((prev_x, prev_y))
-                            .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
-
-                        (
-                            (mx_i as isize, my_i as isize),
-                            (px_i as isize, py_i as isize),
-                        )
-                    })
-                    .unwrap_or_default();
-
-                for pixel_pos in line_drawing::Bresenham::new(mouse_prev_cell, mouse_cell) {
-                    let (pixel_x, pixel_y) = (pixel_pos.0 as i32, pixel_pos.1 as i32);
-                    for x_off in -1..=1 {
-                        for y_off in -1..=1 {
-                            world.set_pixel(
-                                (
-                                    (pixel_x + x_off) as usize,
-                                    (pixel_y + y_off - TOOLBAR_HEIGHT as i32) as usize,
-                                ),
-                                click_kind,
-                            );
+                // Dragging with the wind tool blows the way the cursor moves
+                if tool == Tool::Wind && input.mouse_held(0) && !over_timeline {
+                    if let (Some(cell), Some((prev, _))) = (
+                        mouse_cell(&pixels, &input, &camera),
+                        mouse_view_move(&pixels, &input),
+                    ) {
+                        let (px, py) = camera.cell_at(prev);
+                        world.gust(
+                            cell,
+                            (
+                                (cell.0 as i32 - px as i32) * WIND_GUST,
+                                (cell.1 as i32 - py as i32) * WIND_GUST,
+                            ),
+                        );
+                    }
+                }
+
+                let left_click = tool == Tool::Brush && input.mouse_held(0) && !over_timeline;
+                let right_click = input.mouse_held(1) && !over_timeline;
+
+                if left_click || right_click {
+                    let click_kind = if left_click {
+                        selected_kind
+                    } else {
+                        Kind::Empty
+                    };
+
+                    // Nothing is painted while the mouse is over the toolbar
+                    if let Some((prev, now)) = mouse_view_move(&pixels, &input) {
+                        let (px, py) = camera.cell_at(prev);
+                        let (mx, my) = camera.cell_at(now);
+                        for cell in line_drawing::Bresenham::new(
+                            (px as isize, py as isize),
+                            (mx as isize, my as isize),
+                        ) {
+                            let (cell_x, cell_y) = (cell.0 as i32, cell.1 as i32);
+                            for x_off in -1..=1 {
+                                for y_off in -1..=1 {
+                                    world.set_pixel(
+                                        ((cell_x + x_off) as usize, (cell_y + y_off) as usize),
+                                        click_kind,
+                                    );
+                                }
+                            }
                         }
                     }
                 }
+
+                // Hold P to pour the selected kind from the top edge above the cursor
+                if input.key_pressed(VirtualKeyCode::Comma) {
+                    pour_width = pour_width
+                        .saturating_sub(POUR_WIDTH_STEP)
+                        .max(POUR_WIDTH_STEP);
+                } else if input.key_pressed(VirtualKeyCode::Period) {
+                    pour_width = (pour_width + POUR_WIDTH_STEP).min(world.width());
+                }
+                if input.key_held(VirtualKeyCode::P) {
+                    let center = input
+                        .mouse()
+                        .and_then(|mouse_pos| pixels.window_pos_to_pixel(mouse_pos).ok())
+                        .and_then(view_pos)
+                        .map_or(camera.cell_at((VIEW_WIDTH as usize / 2, 0)).0, |pos| {
+                            camera.cell_at(pos).0
+                        });
+                    let start = center
+                        .saturating_sub(pour_width / 2)
+                        .min(world.width() - pour_width);
+                    world.pour(selected_kind, start..start + pour_width);
+                }
+
+                // - and = slow the simulation down and speed it up
+                if input.key_pressed(VirtualKeyCode::Minus) {
+                    speed = speed.saturating_sub(1);
+                } else if input.key_pressed(VirtualKeyCode::Equals) {
+                    speed = (speed + 1).min(SPEEDS.len() - 1);
+                }
             }
 
-            // Update internal state and request a redraw
-            if !paused || input.key_pressed(VirtualKeyCode::F) {
-                world.update();
+            // Run whichever ticks have come due, or a single one when stepping
+            // frame by frame, once every event waiting has been handled. This
+            // comes round whether or not any of them were input, and again when
+            // the wait for the next tick is up.
+            if let Event::MainEventsCleared = event {
+                let tick_duration =
+                    Duration::from_secs_f64(1.0 / (tick_rate as f64 * SPEEDS[speed]));
+                let now = Instant::now();
+                lag = (lag + (now - last_step))
+                    .min(tick_duration.mul_f64(MAX_CATCH_UP_TICKS * SPEEDS[speed].max(1.0)));
+                last_step = now;
+                let mut due = 0;
+                if paused {
+                    lag = Duration::ZERO;
+                    due = input.key_pressed(VirtualKeyCode::F) as u32;
+                } else {
+                    while lag >= tick_duration {
+                        lag -= tick_duration;
+                        due += 1;
+                    }
+                }
+
+                // Update internal state and request a redraw
+                for _ in 0..due {
+                    before_tick = Some(world.clone());
+                    world.update();
+                    before_tick = None;
+                    if world.tick().is_multiple_of(history::CHECKPOINT_INTERVAL) {
+                        history.record(&world);
+                    }
+                    for line in world.trace.drain(..) {
+                        println!("{}", line);
+                    }
+                }
+
+                window.request_redraw();
+                *control_flow = ControlFlow::WaitUntil(now + tick_duration - lag);
             }
+        }));
 
-            window.request_redraw();
+        // A panic anywhere, in the rules, drawing or handling input, leaves
+        // the world as it was when things went wrong, which is worth keeping
+        // for whoever has to fix it
+        if handled.is_err() {
+            match crash::write_dump(before_tick.as_ref().unwrap_or(&world), &input_log) {
+                Ok(dir) => eprintln!(
+                    "The simulation crashed. The world and recent input were saved to {}; \
+                     please attach that directory to a bug report. Running with \
+                     `--dump {}` loads the world from it.",
+                    dir.display(),
+                    dir.display()
+                ),
+                Err(e) => eprintln!("The simulation crashed, and saving it failed: {}", e),
+            }
+            *control_flow = ControlFlow::Exit;
         }
     });
 }
+
+#[cfg(test)]
+mod api {
+    use super::*;
+
+    #[test]
+    fn every_tool_has_its_own_key() {
+        let tools = [
+            Tool::Brush,
+            Tool::Fill,
+            Tool::Replace,
+            Tool::Measure,
+            Tool::Watch,
+            Tool::Wind,
+        ];
+        for tool in tools {
+            assert_eq!(
+                TOOL_KEYS.iter().filter(|&&(_, t)| t == tool).count(),
+                1,
+                "{:?}",
+                tool
+            );
+        }
+        for (i, &(key, _)) in TOOL_KEYS.iter().enumerate() {
+            assert!(TOOL_KEYS[i + 1..].iter().all(|&(other, _)| other != key));
+            assert!(!NUM_KEYS.contains(&key));
+        }
+    }
+
+    #[test]
+    fn zooming_keeps_the_cell_under_the_cursor() {
+        let mut camera = Camera::new((VIEW_WIDTH as usize * 2, VIEW_HEIGHT as usize * 2));
+        let cursor = (100, 80);
+        let cell = camera.cell_at(cursor);
+        camera.zoom_at(cursor, true);
+        assert_eq!(camera.cell_at(cursor), cell);
+        camera.zoom_at(cursor, false);
+        assert_eq!(camera.cell_at(cursor), cell);
+    }
+}
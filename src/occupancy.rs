@@ -0,0 +1,44 @@
+pub const WORD_BITS: usize = 64;
+
+/// One bit per cell, set when the cell isn't empty, packed into 64-cell words
+/// so that whole empty spans of a row can be skipped with a single comparison
+#[derive(Clone)]
+pub struct Occupancy {
+    width: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl Occupancy {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        Self {
+            width,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn words_per_row(&self) -> usize {
+        self.words_per_row
+    }
+
+    pub fn set(&mut self, (x, y): (usize, usize), occupied: bool) {
+        let word = &mut self.words[y * self.words_per_row + x / WORD_BITS];
+        let bit = 1 << (x % WORD_BITS);
+        if occupied {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    pub fn word(&self, y: usize, word: usize) -> u64 {
+        self.words[y * self.words_per_row + word]
+    }
+
+    /// The cells covered by one of a row's words
+    pub fn span(&self, word: usize) -> std::ops::Range<usize> {
+        word * WORD_BITS..((word + 1) * WORD_BITS).min(self.width)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::PRESSURE_BLOCK_SIZE;
+
+/// Air pressure over blocks of the grid. Pressure spreads out into the
+/// neighboring blocks and leaks away a little every step.
+#[derive(Clone)]
+pub struct PressureField {
+    wide: usize,
+    high: usize,
+    blocks: Vec<i32>,
+}
+
+impl PressureField {
+    pub fn new(width: usize, height: usize) -> Self {
+        let (wide, high) = (
+            width.div_ceil(PRESSURE_BLOCK_SIZE),
+            height.div_ceil(PRESSURE_BLOCK_SIZE),
+        );
+        Self {
+            wide,
+            high,
+            blocks: vec![0; wide * high],
+        }
+    }
+
+    fn block((x, y): (usize, usize)) -> (usize, usize) {
+        (x / PRESSURE_BLOCK_SIZE, y / PRESSURE_BLOCK_SIZE)
+    }
+
+    fn get(&self, (bx, by): (i32, i32)) -> i32 {
+        let bx = bx.clamp(0, self.wide as i32 - 1) as usize;
+        let by = by.clamp(0, self.high as i32 - 1) as usize;
+        self.blocks[by * self.wide + bx]
+    }
+
+    pub fn at(&self, pos: (usize, usize)) -> i32 {
+        let (bx, by) = Self::block(pos);
+        self.blocks[by * self.wide + bx]
+    }
+
+    pub fn add(&mut self, pos: (usize, usize), amount: i32) {
+        let (bx, by) = Self::block(pos);
+        self.blocks[by * self.wide + bx] += amount;
+    }
+
+    /// Copies the blocks starting `origin` cells across, which has to be the
+    /// edge of a block, into a narrower field `cut`, carrying on round from
+    /// the left edge past the right
+    pub fn cut_into(&self, origin: usize, cut: &mut Self) {
+        let first = origin / PRESSURE_BLOCK_SIZE;
+        for by in 0..self.high {
+            for bx in 0..cut.wide {
+                cut.blocks[by * cut.wide + bx] =
+                    self.blocks[by * self.wide + (first + bx) % self.wide];
+            }
+        }
+    }
+
+    /// Copies a field `cut_into` one from this one back over where it came from
+    pub fn paste(&mut self, origin: usize, cut: &Self) {
+        let first = origin / PRESSURE_BLOCK_SIZE;
+        for by in 0..self.high {
+            for bx in 0..cut.wide {
+                self.blocks[by * self.wide + (first + bx) % self.wide] =
+                    cut.blocks[by * cut.wide + bx];
+            }
+        }
+    }
+
+    /// Which way the pressure around a cell pushes, as the difference between
+    /// the blocks on either side of its block horizontally and vertically.
+    /// Positive means towards the right or down.
+    pub fn push(&self, pos: (usize, usize)) -> (i32, i32) {
+        let (bx, by) = Self::block(pos);
+        let (bx, by) = (bx as i32, by as i32);
+        (
+            self.get((bx - 1, by)) - self.get((bx + 1, by)),
+            self.get((bx, by - 1)) - self.get((bx, by + 1)),
+        )
+    }
+
+    /// Spreads pressure out into the neighboring blocks and lets one part in
+    /// `leak` of it escape
+    pub fn step(&mut self, leak: i32) {
+        let before = self.blocks.clone();
+        let (wide, high) = (self.wide, self.high);
+        for by in 0..high {
+            for bx in 0..wide {
+                let i = by * wide + bx;
+                let mut spread = 0;
+                for (nx, ny) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, ny) = (bx as i32 + nx, by as i32 + ny);
+                    if nx >= 0 && nx < wide as i32 && ny >= 0 && ny < high as i32 {
+                        spread += before[ny as usize * wide + nx as usize] - before[i];
+                    }
+                }
+                let pressure = before[i] + spread / 8;
+                self.blocks[i] = pressure - pressure / leak;
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+#[cfg(feature = "profile")]
+use std::time::Instant;
+
+/// When a phase started. Without the `profile` feature there's nothing to
+/// remember, and timing phases costs nothing.
+pub(crate) struct Span {
+    #[cfg(feature = "profile")]
+    start: Instant,
+}
+
+pub(crate) fn start() -> Span {
+    Span {
+        #[cfg(feature = "profile")]
+        start: Instant::now(),
+    }
+}
+
+/// How long each phase of the last update, and any drawing since, took.
+/// Only filled in when built with the `profile` feature.
+#[derive(Clone, Default)]
+pub struct Profile {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Profile {
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.phases.clear();
+    }
+
+    // Replaces any earlier timing of the same phase, so that drawing while
+    // paused doesn't pile up timings
+    #[cfg_attr(not(feature = "profile"), allow(unused_variables))]
+    pub(crate) fn record(&mut self, name: &'static str, span: Span) {
+        #[cfg(feature = "profile")]
+        {
+            let time = span.start.elapsed();
+            match self.phases.iter_mut().find(|(phase, _)| *phase == name) {
+                Some(phase) => phase.1 = time,
+                None => self.phases.push((name, time)),
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{Kind, Particle, World};
+
+const HEADER_LEN: usize = 8 + 4 + 4 + 4 + 16;
+const CELL_LEN: usize = 8;
+
+/// Writes a world to a file as a small header (seed, tick, width, height and
+/// how far its random number generator has got) followed by eight bytes per
+/// cell: kind, flags, counter, stored kind,
+/// temperature and velocity
+pub fn write(world: &World, path: &Path) -> io::Result<()> {
+    let (width, height) = (world.width(), world.height());
+    let mut bytes = Vec::with_capacity(HEADER_LEN + width * height * CELL_LEN);
+    bytes.extend(world.seed.to_le_bytes());
+    bytes.extend(world.tick.to_le_bytes());
+    bytes.extend((width as u32).to_le_bytes());
+    bytes.extend((height as u32).to_le_bytes());
+    bytes.extend(world.rng.get_word_pos().to_le_bytes());
+    for y in 0..height {
+        for x in 0..width {
+            let particle = world.particles.get((x, y));
+            bytes.extend([
+                particle.kind,
+                particle.flags,
+                particle.counter,
+                particle.stored,
+            ]);
+            bytes.extend(particle.temperature.to_le_bytes());
+            bytes.extend([particle.velocity.0 as u8, particle.velocity.1 as u8]);
+        }
+    }
+    fs::write(path, bytes)
+}
+
+/// Rebuilds a world written by `write`, down to where its random number
+/// generator had got to, so it plays on exactly as it would have
+pub fn read(path: &Path) -> io::Result<World> {
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} {}", path.display(), what),
+        )
+    };
+
+    let bytes = fs::read(path)?;
+    if bytes.len() < HEADER_LEN {
+        return Err(invalid("is too short to be a save"));
+    }
+    let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    let (width, height) = (word(12), word(16));
+    if width == 0 || height == 0 || bytes.len() != HEADER_LEN + width * height * CELL_LEN {
+        return Err(invalid("is the wrong size for its grid"));
+    }
+    let kind = |byte: u8| {
+        Kind::ALL
+            .get(byte as usize)
+            .copied()
+            .ok_or_else(|| invalid("contains an unknown kind"))
+    };
+
+    let seed = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+    let mut world = World::with_size((width, height), seed);
+    world.tick = word(8) as u32;
+    world
+        .rng
+        .set_word_pos(u128::from_le_bytes(bytes[20..36].try_into().unwrap()));
+    for (i, cell) in bytes[HEADER_LEN..].chunks_exact(CELL_LEN).enumerate() {
+        let pos = (i % width, i / width);
+        world.replace(
+            pos,
+            Particle {
+                flags: cell[1],
+                ..Particle::new(kind(cell[0])?)
+                    .with_counter(cell[2])
+                    .with_stored(kind(cell[3])?)
+                    .with_temperature(i16::from_le_bytes([cell[4], cell[5]]))
+                    .with_velocity((cell[6] as i8, cell[7] as i8))
+            },
+        );
+    }
+    Ok(world)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GRID_HEIGHT;
+
+    #[test]
+    fn saved_worlds_play_on_the_same() {
+        let mut world = World::with_seed(3);
+        world.pour(Kind::Sand, 100..140);
+        world.pour(Kind::Water, 160..200);
+        for _ in 0..GRID_HEIGHT {
+            world.update();
+        }
+        let path = std::env::temp_dir().join(format!("save-test-{}.bin", std::process::id()));
+        write(&world, &path).unwrap();
+        let mut loaded = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for _ in 0..100 {
+            world.update();
+            loaded.update();
+        }
+        assert!(world.differences(&loaded).is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{Kind, Particle, World};
+
+const EMITTER_KINDS: [Kind; 4] = [Kind::Water, Kind::Sand, Kind::Salt, Kind::Slime];
+const EMITTER_SIZE: usize = 3;
+
+/// Builds a small random scene of `width` by `height` cells: hilly terrain, a
+/// couple of emitters pouring something onto it and one hazard. The same seed
+/// always builds the same scene, and the world it returns is seeded with it so
+/// it plays out the same.
+pub fn generate((width, height): (usize, usize), seed: u64) -> World {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut world = World::with_size((width, height), seed);
+
+    // Stone hills under a layer of sand or gravel, following a random walk
+    let topsoil = if rng.gen() { Kind::Sand } else { Kind::Gravel };
+    let mut ground = rng.gen_range(height * 2 / 3..height - 10) as i32;
+    for x in 0..width {
+        ground = (ground + rng.gen_range(-1..=1)).clamp(height as i32 / 2, height as i32 - 4);
+        for y in ground as usize..height {
+            let kind = if y < ground as usize + 3 {
+                topsoil
+            } else {
+                Kind::Stone
+            };
+            world.set_pixel((x, y), kind);
+        }
+    }
+
+    // Clone blocks primed to pour out something
+    for _ in 0..2 {
+        let kind = EMITTER_KINDS[rng.gen_range(0..EMITTER_KINDS.len())];
+        let (x0, y0) = (
+            rng.gen_range(0..width - EMITTER_SIZE),
+            rng.gen_range(4..height / 4),
+        );
+        for y in y0..y0 + EMITTER_SIZE {
+            for x in x0..x0 + EMITTER_SIZE {
+                world.replace((x, y), Particle::new(Kind::Clone).with_stored(kind));
+            }
+        }
+    }
+
+    if rng.gen() {
+        // A blob of lava dropped from above
+        let (cx, cy): (i32, i32) = (rng.gen_range(10..width - 10) as i32, 12);
+        for y in cy - 6..=cy + 6 {
+            for x in cx - 6..=cx + 6 {
+                if (x - cx).pow(2) + (y - cy).pow(2) <= 36 {
+                    world.set_pixel((x as usize, y as usize), Kind::Lava);
+                }
+            }
+        }
+    } else {
+        world.replace(
+            (rng.gen_range(0..width), 0),
+            Particle::new(Kind::Meteor).with_counter(rng.gen_range(0..=1)),
+        );
+    }
+
+    world
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GRID_HEIGHT, GRID_WIDTH};
+
+    #[test]
+    fn seeded_scenes_play_out_the_same() {
+        let run = || {
+            let mut world = generate((GRID_WIDTH as usize, GRID_HEIGHT as usize), 7);
+            for _ in 0..30 {
+                world.update();
+            }
+            (0..GRID_HEIGHT as usize)
+                .flat_map(|y| (0..GRID_WIDTH as usize).map(move |x| (x, y)))
+                .map(|pos| world.particle(pos).kind())
+                .collect::<Vec<Kind>>()
+        };
+        assert_eq!(run(), run());
+    }
+}
@@ -0,0 +1,315 @@
+use std::ops::Range;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::debugger::TracedRng;
+use crate::occupancy::WORD_BITS;
+use crate::{
+    Boundary, Shockwave, World, CHUNK_SIZE, PRESSURE_BLOCK_SIZE, STRIP_MARGIN, WIND_BLOCK_SIZE,
+};
+
+// Strips are cut along block edges, so their air is whole blocks of the world's
+const _: () = assert!(STRIP_MARGIN.is_multiple_of(WIND_BLOCK_SIZE));
+const _: () = assert!(STRIP_MARGIN.is_multiple_of(PRESSURE_BLOCK_SIZE));
+
+/// Where a strip cut out of a wider world to update on a thread of its own
+/// sits in that world
+#[derive(Clone)]
+pub struct Strip {
+    // How far across the wider world the strip's first column is, and how
+    // wide that world is. A strip cut across the seam of a wrapped world
+    // carries on from its left edge.
+    pub origin: usize,
+    pub world_width: usize,
+    // Whether the wider world's sides join up, so that both of the strip's
+    // sides are cuts
+    pub wraps: bool,
+    // The strip's own columns, whose particles get their turn. The rest are
+    // a margin for them to move into.
+    pub columns: Range<usize>,
+    // Whether each chunk down the strip's own columns is awake and has
+    // anything in it to update
+    pub awake: Vec<bool>,
+}
+
+impl Strip {
+    /// Whether a cell in a strip `width` by `height` cells is nearer one of the
+    /// wider world's edges than a side where the strip was cut from the rest
+    /// of it
+    pub fn nearer_an_edge(&self, (x, y): (usize, usize), (width, height): (usize, usize)) -> bool {
+        let (left, right) = (x, width - 1 - x);
+        let cut_left = self.wraps || self.origin > 0;
+        let cut_right = self.wraps || self.origin + width < self.world_width;
+        let to_edge = [
+            Some(y),
+            Some(height - 1 - y),
+            (!cut_left).then_some(left),
+            (!cut_right).then_some(right),
+        ];
+        let to_cut = [cut_left.then_some(left), cut_right.then_some(right)];
+        let nearest = |distances: &[Option<usize>]| {
+            distances
+                .iter()
+                .flatten()
+                .min()
+                .copied()
+                .unwrap_or(usize::MAX)
+        };
+        nearest(&to_edge) < nearest(&to_cut)
+    }
+}
+
+/// Strips left over from the last parallel update, to cut the next ones into
+/// rather than setting up new ones every tick
+#[derive(Default)]
+pub struct SpareStrips(Vec<World>);
+
+// A copy of the world starts out without any, and makes its own when it needs them
+impl Clone for SpareStrips {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl World {
+    // Updates the particles a column of chunks at a time, on as many threads
+    // as there are cores. Every other column goes at once, then the rest, so
+    // no two strips being updated together ever overlap. Each strip draws
+    // from its own generator seeded from `rng`, and they're pasted back in
+    // order, so the result doesn't depend on how the threads are scheduled.
+    pub(crate) fn update_strips(&mut self, rng: &mut impl Rng) {
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        let columns = self.width().div_ceil(CHUNK_SIZE);
+        // Strips only reach round a wrapped world if every column is a whole
+        // chunk wide and there's room for a strip and its margins
+        let wraps = self.boundary == Boundary::Wrap
+            && self.width().is_multiple_of(CHUNK_SIZE)
+            && columns > 1;
+        // The first and last columns meet at the seam, so if there's an odd
+        // number of them the last goes on its own
+        let pass = |column: usize| {
+            if wraps && columns % 2 == 1 && column == columns - 1 {
+                2
+            } else {
+                column % 2
+            }
+        };
+        let mut spares = std::mem::take(&mut self.spare_strips);
+        for this_pass in 0..3 {
+            let mut strips: Vec<World> = (0..columns)
+                .filter(|&column| pass(column) == this_pass)
+                .filter_map(|column| self.cut_strip(column, rng.gen(), wraps, &mut spares.0))
+                .collect();
+            let per_thread = strips.len().div_ceil(threads).max(1);
+            std::thread::scope(|scope| {
+                for strips in strips.chunks_mut(per_thread) {
+                    scope.spawn(move || {
+                        for strip in strips {
+                            strip.update_strip();
+                        }
+                    });
+                }
+            });
+            for strip in &mut strips {
+                self.paste_strip(strip);
+            }
+            spares.0.extend(strips);
+        }
+        self.spare_strips = spares;
+    }
+
+    // Copies a column of chunks, and as much of the world either side of it as
+    // its particles could reach, into a world of its own, reusing one of
+    // `spares` the right size if there is one. Returns nothing if there's
+    // nothing in the column to update, because it's empty or asleep.
+    fn cut_strip(
+        &self,
+        column: usize,
+        seed: u64,
+        wraps: bool,
+        spares: &mut Vec<World>,
+    ) -> Option<World> {
+        let columns = column * CHUNK_SIZE..((column + 1) * CHUNK_SIZE).min(self.width());
+        // A chunk is never split between occupancy words
+        let occupied = |y: usize| {
+            let word = self.occupancy.word(y, columns.start / WORD_BITS);
+            (word >> (columns.start % WORD_BITS)) & (u64::MAX >> (WORD_BITS - columns.len())) != 0
+        };
+        let awake: Vec<bool> = (0..self.height().div_ceil(CHUNK_SIZE))
+            .map(|chunk| {
+                let rows = chunk * CHUNK_SIZE..((chunk + 1) * CHUNK_SIZE).min(self.height());
+                !self.asleep((columns.start, rows.start)) && rows.into_iter().any(occupied)
+            })
+            .collect();
+        if !awake.contains(&true) {
+            return None;
+        }
+        let (left, right) = if wraps {
+            (STRIP_MARGIN, STRIP_MARGIN)
+        } else {
+            (
+                columns.start.min(STRIP_MARGIN),
+                (self.width() - columns.end).min(STRIP_MARGIN),
+            )
+        };
+        let origin = (columns.start + self.width() - left) % self.width();
+        let size = (left + columns.len() + right, self.height());
+
+        let mut strip = match spares
+            .iter()
+            .position(|spare| (spare.width(), spare.height()) == size)
+        {
+            Some(i) => spares.swap_remove(i),
+            None => World::with_size(size, seed),
+        };
+        strip.components.clear();
+        for y in 0..strip.height() {
+            for x in 0..strip.width() {
+                let particle = self.particles.get(((origin + x) % self.width(), y));
+                strip.particles.set((x, y), particle);
+                strip.occupancy.set((x, y), !particle.empty());
+            }
+        }
+        self.gravity.cut_into(origin, &mut strip.gravity);
+        self.pressure.cut_into(origin, &mut strip.pressure);
+        self.wind.cut_into(origin, &mut strip.wind);
+        strip.changes.clear();
+        strip.shockwaves.clear();
+        strip.trace.clear();
+        strip.rng = ChaCha12Rng::seed_from_u64(seed);
+        strip.pull = self.pull;
+        // A strip's own sides are as far as it reaches
+        strip.boundary = match self.boundary {
+            Boundary::Wrap => Boundary::Wall,
+            boundary => boundary,
+        };
+        strip.edge_temperatures = self.edge_temperatures;
+        strip.grate_pore_size = self.grate_pore_size;
+        strip.lod = self.lod;
+        strip.lod_focus = self.lod_focus;
+        strip.shuffled_order = self.shuffled_order;
+        strip.tick = self.tick;
+        strip.watch = self
+            .watch
+            .filter(|&(x, _)| columns.contains(&x))
+            .map(|(x, y)| ((x + self.width() - origin) % self.width(), y));
+        strip.strip = Some(Strip {
+            origin,
+            world_width: self.width(),
+            wraps,
+            columns: left..left + columns.len(),
+            awake,
+        });
+        Some(strip)
+    }
+
+    // Gives the particles in a strip's own columns their turn
+    fn update_strip(&mut self) {
+        let mut rng = self.update_particles(TracedRng::new(self.rng.clone()));
+        if let Some(draws) = rng.trace(false) {
+            self.trace.push(format!("random draws: {:?}", draws));
+        }
+    }
+
+    // Copies everything a strip changed back over the part of the world it
+    // was cut from
+    fn paste_strip(&mut self, strip: &mut World) {
+        let Some(Strip { origin, .. }) = strip.strip else {
+            return;
+        };
+        let width = self.width();
+        for y in 0..strip.height() {
+            for x in 0..strip.width() {
+                let pos = ((origin + x) % width, y);
+                let (particle, old) = (strip.particles.get((x, y)), self.particles.get(pos));
+                if particle.kind() != old.kind() {
+                    self.replace(pos, particle);
+                } else if particle != old {
+                    self.modify(pos, |old| *old = particle);
+                }
+            }
+        }
+        self.pressure.paste(origin, &strip.pressure);
+        self.shockwaves
+            .extend(strip.shockwaves.drain(..).map(|shockwave| Shockwave {
+                center: (
+                    ((shockwave.center.0 as usize + origin) % width) as i32,
+                    shockwave.center.1,
+                ),
+                ..shockwave
+            }));
+        self.trace.append(&mut strip.trace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scene, Kind, GRID_HEIGHT, GRID_WIDTH};
+
+    #[test]
+    fn parallel_updates_play_out_the_same() {
+        let run = || {
+            let mut world = scene::generate((GRID_WIDTH as usize, GRID_HEIGHT as usize), 7);
+            world.parallel = true;
+            for _ in 0..30 {
+                world.update();
+            }
+            (0..GRID_HEIGHT as usize)
+                .flat_map(|y| (0..GRID_WIDTH as usize).map(move |x| (x, y)))
+                .map(|pos| world.particle(pos).kind())
+                .collect::<Vec<Kind>>()
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn parallel_updates_keep_sand_piling_across_strips() {
+        let mut world = World::with_seed(5);
+        world.parallel = true;
+        // Dropped right over where two columns of chunks meet
+        let source = CHUNK_SIZE * 2;
+        for y in 0..30 {
+            for x in source - 10..source + 10 {
+                world.set_pixel((x, y), Kind::Sand);
+            }
+        }
+        for _ in 0..GRID_HEIGHT * 2 {
+            world.update();
+        }
+        let sand: Vec<(usize, usize)> = (0..GRID_HEIGHT as usize)
+            .flat_map(|y| (0..GRID_WIDTH as usize).map(move |x| (x, y)))
+            .filter(|&pos| world.particle(pos).kind() == Kind::Sand)
+            .collect();
+        assert_eq!(sand.len(), 30 * 20);
+        assert!(sand.iter().any(|&(x, _)| x < source - CHUNK_SIZE / 2));
+        assert!(sand.iter().any(|&(x, _)| x >= source + CHUNK_SIZE / 2));
+    }
+
+    #[test]
+    fn parallel_updates_keep_sand_piling_across_a_wrapped_seam() {
+        // An odd number of chunks across, so the seam falls between two
+        // columns that would otherwise update at the same time
+        let (width, height) = (CHUNK_SIZE * 5, 100);
+        let mut world = World::with_size((width, height), 5);
+        world.boundary = Boundary::Wrap;
+        world.parallel = true;
+        // Dropped up against the left edge, so it can only spread that way
+        // round the seam
+        for y in 0..30 {
+            for x in 0..20 {
+                world.set_pixel((x, y), Kind::Sand);
+            }
+        }
+        for _ in 0..height * 2 {
+            world.update();
+        }
+        let sand: Vec<(usize, usize)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&pos| world.particle(pos).kind() == Kind::Sand)
+            .collect();
+        assert_eq!(sand.len(), 30 * 20);
+        assert!(sand.iter().any(|&(x, _)| x >= width / 2));
+    }
+}
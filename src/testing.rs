@@ -0,0 +1,9 @@
+use crate::{Kind, World};
+
+/// How many cells of the world hold a particle of `kind`
+pub fn count_kind(world: &World, kind: Kind) -> usize {
+    (0..world.height())
+        .flat_map(|y| (0..world.width()).map(move |x| (x, y)))
+        .filter(|&pos| world.particle(pos).kind() == kind)
+        .count()
+}
@@ -0,0 +1,77 @@
+use crate::WIND_BLOCK_SIZE;
+
+/// How fast the air is moving over blocks of the grid, rightwards and
+/// downwards. Gusts spread out into the neighboring blocks and die down a
+/// little every step.
+#[derive(Clone)]
+pub struct WindField {
+    wide: usize,
+    high: usize,
+    blocks: Vec<(i32, i32)>,
+}
+
+impl WindField {
+    pub fn new(width: usize, height: usize) -> Self {
+        let (wide, high) = (
+            width.div_ceil(WIND_BLOCK_SIZE),
+            height.div_ceil(WIND_BLOCK_SIZE),
+        );
+        Self {
+            wide,
+            high,
+            blocks: vec![(0, 0); wide * high],
+        }
+    }
+
+    fn index(&self, (x, y): (usize, usize)) -> usize {
+        (y / WIND_BLOCK_SIZE) * self.wide + x / WIND_BLOCK_SIZE
+    }
+
+    pub fn at(&self, pos: (usize, usize)) -> (i32, i32) {
+        self.blocks[self.index(pos)]
+    }
+
+    /// Copies the blocks starting `origin` cells across, which has to be the
+    /// edge of a block, into a narrower field `cut`, carrying on round from
+    /// the left edge past the right
+    pub fn cut_into(&self, origin: usize, cut: &mut Self) {
+        let first = origin / WIND_BLOCK_SIZE;
+        for by in 0..self.high {
+            for bx in 0..cut.wide {
+                cut.blocks[by * cut.wide + bx] =
+                    self.blocks[by * self.wide + (first + bx) % self.wide];
+            }
+        }
+    }
+
+    pub fn gust(&mut self, pos: (usize, usize), (vx, vy): (i32, i32)) {
+        let i = self.index(pos);
+        let block = &mut self.blocks[i];
+        block.0 += vx;
+        block.1 += vy;
+    }
+
+    /// Spreads the wind out into the neighboring blocks and takes away one
+    /// part in `calm` of it, and at least a little, so that it does die down
+    pub fn step(&mut self, calm: i32) {
+        let before = self.blocks.clone();
+        let (wide, high) = (self.wide, self.high);
+        for by in 0..high {
+            for bx in 0..wide {
+                let i = by * wide + bx;
+                let (mut spread_x, mut spread_y) = (0, 0);
+                for (nx, ny) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, ny) = (bx as i32 + nx, by as i32 + ny);
+                    if nx >= 0 && nx < wide as i32 && ny >= 0 && ny < high as i32 {
+                        let neighbor = before[ny as usize * wide + nx as usize];
+                        spread_x += neighbor.0 - before[i].0;
+                        spread_y += neighbor.1 - before[i].1;
+                    }
+                }
+                let (vx, vy) = (before[i].0 + spread_x / 8, before[i].1 + spread_y / 8);
+                let calm_down = |v: i32| v - v / calm - v.signum();
+                self.blocks[i] = (calm_down(vx), calm_down(vy));
+            }
+        }
+    }
+}